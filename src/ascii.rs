@@ -0,0 +1,355 @@
+//! Compact ASCII notation for boards, one character per tile:
+//!
+//! ```text
+//! name: 2. Gravity
+//! |A..|
+//! |B..|
+//! |...|
+//! -: gravity=0000 size=9 color=yellow_1
+//! A: gravity=0001 removable=false color=white
+//! B: gravity=0001 removable=false color=red_light
+//! ```
+//!
+//! Rows are wrapped in `|`, with `.` for an empty tile and `*` for a collision. The legend below
+//! lists the planets in stack order, taking the same fields as a `planet:` line in a level file:
+//!
+//! - `-`: a pending planet, still on the stack.
+//! - `~`: a planet absorbed by a black hole or merged into another one, gone from the board.
+//! - A letter or digit: a planet placed on every tile showing that symbol.
+//! - `*`: a colliding planet, at its `tile=x,y` field.
+//! - `+`: a planet that left the grid, at its `tile=x,y` field.
+//!
+//! The `name:` line and a `rules:` line, as in a level file, are optional. [`Board`] implements
+//! `Display` with this notation, so tests and tools can print the state of a level.
+
+use std::fmt;
+
+use crate::{
+    level_file::{
+        format_gravity, format_range, format_rules, parse_planet, parse_rules, LevelDef,
+        ParseError, PlanetDef,
+    },
+    rules::RuleSet,
+    sim::{Board, Body, Kind, PlanetState, Tile, MASS_DEFAULT},
+};
+
+const SYMBOLS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+pub fn parse(file: &str, source: &str) -> Result<LevelDef, ParseError> {
+    let mut name = String::new();
+    let mut rules = RuleSet::default();
+    let mut rows: Vec<(usize, Vec<char>)> = Vec::new();
+    let mut legend: Vec<(usize, char, &str)> = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(row) = line.strip_prefix('|') {
+            let row = match row.strip_suffix('|') {
+                Some(row) => row,
+                None => return Err(ParseError::new(file, line_number, "row", "missing `|`")),
+            };
+            if !legend.is_empty() {
+                return Err(ParseError::new(
+                    file,
+                    line_number,
+                    "row",
+                    "rows must come before the legend",
+                ));
+            }
+            rows.push((line_number, row.chars().collect()));
+        } else if let Some(value) = line.strip_prefix("name:") {
+            name = value.trim().to_string();
+        } else if let Some(fields) = line.strip_prefix("rules:") {
+            rules = parse_rules(file, line_number, fields)?;
+        } else {
+            let (symbol, fields) = match line.split_once(':') {
+                Some((symbol, fields)) if symbol.trim().chars().count() == 1 => {
+                    (symbol.trim().chars().next().unwrap_or_default(), fields)
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        file,
+                        line_number,
+                        line,
+                        "expected a `|row|` or a `symbol: fields` legend line",
+                    ))
+                }
+            };
+            legend.push((line_number, symbol, fields));
+        }
+    }
+
+    let width = rows.first().map_or(0, |(_, row)| row.len());
+    if width == 0 {
+        return Err(ParseError::new(file, 0, "row", "missing grid rows"));
+    }
+    for (line_number, row) in &rows {
+        if row.len() != width {
+            return Err(ParseError::new(
+                file,
+                *line_number,
+                "row",
+                format!("expected {} tiles, found {}", width, row.len()),
+            ));
+        }
+        for symbol in row {
+            let is_known =
+                matches!(symbol, '.' | '*') || legend.iter().any(|(_, other, _)| other == symbol);
+            if !is_known {
+                return Err(ParseError::new(
+                    file,
+                    *line_number,
+                    "row",
+                    format!("symbol `{}` is missing from the legend", symbol),
+                ));
+            }
+        }
+    }
+
+    let mut planets = Vec::new();
+    for (line_number, symbol, fields) in legend {
+        let planet = parse_planet(file, line_number, fields)?;
+        let field = symbol.to_string();
+
+        match symbol {
+            '-' | '~' => {
+                if planet.body.state != PlanetState::Pending {
+                    return Err(ParseError::new(
+                        file,
+                        line_number,
+                        &field,
+                        "pending and absorbed planets have no `tile`",
+                    ));
+                }
+                let mut planet = planet;
+                if symbol == '~' {
+                    planet.body.state = PlanetState::Absorbed;
+                }
+                planets.push(planet);
+            }
+            '*' | '+' => {
+                let tile = match planet.body.state {
+                    PlanetState::Placed(tile) => tile,
+                    _ => return Err(ParseError::new(file, line_number, &field, "missing `tile`")),
+                };
+                let mut planet = planet;
+                if symbol == '*' {
+                    planet.body.state = PlanetState::Colliding(tile);
+                }
+                planets.push(planet);
+            }
+            _ if SYMBOLS.contains(symbol) => {
+                if planet.body.state != PlanetState::Pending {
+                    return Err(ParseError::new(
+                        file,
+                        line_number,
+                        &field,
+                        "the grid already gives the `tile`",
+                    ));
+                }
+
+                let mut found = false;
+                for (y, (_, row)) in rows.iter().enumerate() {
+                    for (x, other) in row.iter().enumerate() {
+                        if *other == symbol {
+                            let mut planet = planet.clone();
+                            planet.body.state = PlanetState::Placed(Tile::new(x as i32, y as i32));
+                            planets.push(planet);
+                            found = true;
+                        }
+                    }
+                }
+
+                if !found {
+                    return Err(ParseError::new(
+                        file,
+                        line_number,
+                        &field,
+                        "symbol is not on the grid",
+                    ));
+                }
+            }
+            _ => {
+                return Err(ParseError::new(
+                    file,
+                    line_number,
+                    &field,
+                    "expected `-`, `*`, `+`, a letter or a digit",
+                ))
+            }
+        }
+    }
+
+    Ok(LevelDef {
+        name,
+        grid_tiles: Tile::new(width as i32, rows.len() as i32),
+        rules,
+        planets,
+    })
+}
+
+/// Prints a level, keeping planet sizes and colours.
+pub fn format_level(level_def: &LevelDef) -> String {
+    let mut out = String::new();
+    if !level_def.name.is_empty() {
+        out.push_str(&format!("name: {}\n", level_def.name));
+    }
+    if let Some(rules) = format_rules(&level_def.rules) {
+        out.push_str(&format!("rules: {}\n", rules));
+    }
+
+    let planets: Vec<(Body, Option<&PlanetDef>)> = level_def
+        .planets
+        .iter()
+        .map(|planet| (planet.body, Some(planet)))
+        .collect();
+    out.push_str(&format_planets(level_def.grid_tiles, &planets));
+
+    out
+}
+
+/// Prints a board. Only the fields the simulation cares about are kept.
+pub fn format_board(board: &Board) -> String {
+    let mut out = String::new();
+    if let Some(rules) = format_rules(&board.rules) {
+        out.push_str(&format!("rules: {}\n", rules));
+    }
+
+    let planets: Vec<(Body, Option<&PlanetDef>)> =
+        board.bodies.iter().map(|body| (*body, None)).collect();
+    out.push_str(&format_planets(board.grid_tiles, &planets));
+
+    out
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format_board(self))
+    }
+}
+
+fn format_planets(grid_tiles: Tile, planets: &[(Body, Option<&PlanetDef>)]) -> String {
+    let is_inside =
+        |tile: Tile| tile.x >= 0 && tile.y >= 0 && tile.x < grid_tiles.x && tile.y < grid_tiles.y;
+
+    let mut grid = vec![vec!['.'; grid_tiles.x.max(0) as usize]; grid_tiles.y.max(0) as usize];
+    let mut legend = String::new();
+    let mut symbols = SYMBOLS.chars();
+
+    for (body, planet_def) in planets {
+        let (symbol, tile) = match body.state {
+            PlanetState::Pending => ('-', None),
+            PlanetState::Absorbed => ('~', None),
+            PlanetState::Placed(tile) if is_inside(tile) => {
+                // Ran out of symbols, fall back to listing the tile
+                let symbol = symbols.next().unwrap_or('+');
+                grid[tile.y as usize][tile.x as usize] = symbol;
+                (symbol, (symbol == '+').then_some(tile))
+            }
+            PlanetState::Placed(tile) => ('+', Some(tile)),
+            PlanetState::Colliding(tile) => {
+                if is_inside(tile) {
+                    grid[tile.y as usize][tile.x as usize] = '*';
+                }
+                ('*', Some(tile))
+            }
+        };
+
+        legend.push_str(&format!("{}:", symbol));
+        if body.kind != Kind::default() {
+            legend.push_str(&format!(" kind={}", body.kind.name()));
+        }
+        legend.push_str(&format!(" gravity={}", format_gravity(body.gravity_field)));
+        if body.push_field != 0 {
+            legend.push_str(&format!(" push={}", format_gravity(body.push_field)));
+        }
+        if let Some(range) = format_range(body) {
+            legend.push_str(&format!(" range={}", range));
+        }
+        if body.mass != MASS_DEFAULT {
+            legend.push_str(&format!(" mass={}", body.mass));
+        }
+        if !body.is_removable {
+            legend.push_str(" removable=false");
+        }
+        if body.is_anchored {
+            legend.push_str(" anchored=true");
+        }
+        if let Some(planet_def) = planet_def {
+            legend.push_str(&format!(
+                " size={} color={}",
+                planet_def.size, planet_def.color
+            ));
+        }
+        if let Some(tile) = tile {
+            legend.push_str(&format!(" tile={},{}", tile.x, tile.y));
+        }
+        legend.push('\n');
+    }
+
+    let mut out = String::new();
+    for row in grid {
+        out.push('|');
+        out.extend(row);
+        out.push_str("|\n");
+    }
+    out.push_str(&legend);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+name: Test
+rules: collision=swap
+|A.*|
+|.B.|
+-: gravity=0000 size=9 color=yellow_1
+~: gravity=1000 size=8 color=white
+A: gravity=0001 removable=false size=8 color=white
+B: gravity=0010 mass=2 size=8 color=red_light
+*: gravity=0100 size=8 color=white tile=2,0
++: gravity=1000 size=8 color=white tile=3,1
+";
+
+    #[test]
+    fn format_round_trips() {
+        let level_def = parse("test", SOURCE).unwrap();
+        let formatted = format_level(&level_def);
+        assert_eq!(parse("test", &formatted).unwrap(), level_def);
+        assert_eq!(formatted, SOURCE);
+    }
+
+    #[test]
+    fn plus_places_a_planet_at_its_tile() {
+        let level_def = parse("test", SOURCE).unwrap();
+        let planet = level_def.planets.last().unwrap();
+        assert_eq!(planet.body.state, PlanetState::Placed(Tile::new(3, 1)));
+
+        let error = parse("test", "|.|\n+: gravity=0000").unwrap_err();
+        assert_eq!((error.line, error.field.as_str()), (2, "+"));
+    }
+
+    #[test]
+    fn tilde_parses_as_absorbed() {
+        let level_def = parse("test", SOURCE).unwrap();
+        assert_eq!(level_def.planets[1].body.state, PlanetState::Absorbed);
+
+        let error = parse("test", "|.|\n~: gravity=0000 tile=0,0").unwrap_err();
+        assert_eq!((error.line, error.field.as_str()), (2, "~"));
+    }
+
+    #[test]
+    fn unknown_symbol_is_an_error() {
+        let error = parse("test", "|A.|\n|.B|\nA: gravity=0000").unwrap_err();
+        assert_eq!((error.line, error.field.as_str()), (2, "row"));
+    }
+}
@@ -0,0 +1,47 @@
+//! Prints a generated level in the level file format, ready to be saved under `assets/levels/`.
+//!
+//! ```bash
+//! cargo run --bin generate_level --target x86_64-unknown-linux-gnu -- <seed> [easy|medium|hard]
+//! ```
+
+use std::{env, process::ExitCode};
+
+use akj_21::{
+    generator::{self, Difficulty},
+    level_file,
+};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+
+    let seed = match args.get(1).map(|seed| seed.parse::<u64>()) {
+        Some(Ok(seed)) => seed,
+        _ => {
+            eprintln!("usage: generate_level <seed> [easy|medium|hard]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let difficulty = match args.get(2).map(String::as_str) {
+        None => Difficulty::Medium,
+        Some(name) => match Difficulty::ALL.iter().find(|d| d.name() == name) {
+            Some(difficulty) => *difficulty,
+            None => {
+                eprintln!("unknown difficulty `{}`", name);
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    let mut generated = generator::generate(seed, difficulty);
+    generated.level_def.name = format!("Seed {}", seed);
+
+    println!("# Generated from seed {} ({})", seed, difficulty.name());
+    println!(
+        "# Rating {:.1}: {} actions, branching {:.1}",
+        generated.rating.score, generated.rating.solution_length, generated.rating.branching
+    );
+    print!("{}", level_file::format_level(&generated.level_def));
+
+    ExitCode::SUCCESS
+}
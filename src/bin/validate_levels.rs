@@ -0,0 +1,71 @@
+//! Prints a validation report for every level listed in the level index.
+//!
+//! ```bash
+//! cargo run --bin validate_levels --target x86_64-unknown-linux-gnu -- [levels dir]
+//! ```
+
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use akj_21::{ascii, generator::Rating, level_file, solver::Limits, validate};
+
+fn main() -> ExitCode {
+    let dir = match env::args().nth(1) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/levels"),
+    };
+
+    let index = match fs::read_to_string(dir.join(level_file::INDEX_FILE)) {
+        Ok(index) => index,
+        Err(error) => {
+            eprintln!("{}: {}", dir.join(level_file::INDEX_FILE).display(), error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let files = level_file::parse_index(&index);
+    let mut invalid_count = 0;
+
+    for file in &files {
+        let level_def = match fs::read_to_string(dir.join(file)) {
+            Ok(source) => level_file::parse_level(file, &source),
+            Err(error) => Err(level_file::ParseError::new(file, 0, "", error.to_string())),
+        };
+        let level_def = match level_def {
+            Ok(level_def) => level_def,
+            Err(error) => {
+                println!("FAIL  {}", file);
+                println!("      {}", error);
+                invalid_count += 1;
+                continue;
+            }
+        };
+
+        let report = validate::validate(&level_def, Limits::default());
+        if report.is_valid() {
+            if let Some(rating) = report.search.as_ref().and_then(Rating::from_search) {
+                println!(
+                    "ok    {}: rating {:.1}, {} actions, branching {:.1}",
+                    level_def.name, rating.score, rating.solution_length, rating.branching
+                );
+            }
+            continue;
+        }
+
+        invalid_count += 1;
+        println!("FAIL  {} ({})", level_def.name, file);
+        for issue in &report.issues {
+            println!("      {}", issue);
+        }
+        for line in ascii::format_board(&level_def.board()).lines() {
+            println!("      {}", line);
+        }
+    }
+
+    println!("{} levels, {} invalid", files.len(), invalid_count);
+
+    if invalid_count > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
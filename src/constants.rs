@@ -5,5 +5,18 @@ pub const TILE_SIZE_Y: f32 = TILE_SIZE_PX;
 
 pub const GRID_THICKNESS: f32 = 1.0;
 
+/// Extra radius in pixels for every unit of mass above the default
+pub const MASS_SIZE_STEP: f32 = 1.5;
+
 pub const SCREEN_W: f32 = 512.0;
 pub const SCREEN_H: f32 = 288.0;
+
+pub const HINT_SCORE_COST: i32 = 5;
+/// Boards the hint search visits each frame, so bigger levels don't freeze the game
+pub const HINT_BOARDS_PER_FRAME: usize = 1_000;
+pub const UNDO_SCORE_COST: i32 = 1;
+
+/// Seconds between the steps triggered by a single move
+pub const SIM_STEP_INTERVAL: f64 = 0.25;
+
+pub const LEVELS_DIR: &str = "assets/levels";
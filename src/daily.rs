@@ -0,0 +1,70 @@
+//! Puzzle of the day. Days are counted in UTC, so everyone gets the same puzzle on a given date.
+
+use crate::{
+    generator::{self, Difficulty},
+    level_file::LevelDef,
+};
+
+pub const DIFFICULTY: Difficulty = Difficulty::Medium;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Days since the Unix epoch for a timestamp in seconds.
+pub fn day_from_timestamp(seconds: f64) -> u64 {
+    (seconds / SECONDS_PER_DAY).floor().max(0.0) as u64
+}
+
+/// Year, month and day of a day counted from the Unix epoch, in the proleptic Gregorian
+/// calendar.
+pub fn date(day: u64) -> (i64, u32, u32) {
+    // Shift the epoch to 0000-03-01 so leap days fall at the end of each year
+    let days = day as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+
+    let day_of_month = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day_of_month)
+}
+
+/// The puzzle for a day counted from the Unix epoch.
+pub fn level(day: u64) -> LevelDef {
+    let mut level_def = generator::generate(day, DIFFICULTY).level_def;
+
+    let (year, month, day_of_month) = date(day);
+    level_def.name = format!("Daily {}-{:02}-{:02}", year, month, day_of_month);
+
+    level_def
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_start_at_midnight_utc() {
+        assert_eq!(day_from_timestamp(-1.0), 0);
+        assert_eq!(day_from_timestamp(0.0), 0);
+        assert_eq!(day_from_timestamp(86_399.9), 0);
+        assert_eq!(day_from_timestamp(86_400.0), 1);
+        assert_eq!(day_from_timestamp(1_792_281_600.0), 20_744);
+    }
+
+    #[test]
+    fn dates_follow_the_gregorian_calendar() {
+        assert_eq!(date(0), (1970, 1, 1));
+        assert_eq!(date(11_016), (2000, 2, 29));
+        assert_eq!(date(11_017), (2000, 3, 1));
+        assert_eq!(date(20_744), (2026, 10, 18));
+    }
+}
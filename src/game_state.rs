@@ -1,6 +1,18 @@
+use std::{cell::RefCell, rc::Rc};
+
+use akj_21::{
+    daily,
+    level_file::{self, LevelDef},
+    rules::RuleSet,
+    sim::{Board, PlanetState, Run, Tile},
+    solver::{Action, Solver},
+};
 use macroquad::{
     audio::{load_sound, Sound},
-    math::{f32, IVec2},
+    file::load_string,
+    logging::error,
+    math::f32,
+    miniquad::date,
     shapes::draw_rectangle,
     texture::{load_texture, Texture2D},
     window::clear_background,
@@ -15,20 +27,34 @@ pub struct GameState {
 
     pub mouse_pos: f32::Vec2,
     pub is_mouse_in_grid: bool,
-    pub tile_highlighted_prev: IVec2,
-    pub tile_highlighted: IVec2,
+    pub tile_highlighted_prev: Tile,
+    pub tile_highlighted: Tile,
+    pub is_gravity_overlay_visible: bool,
 
     pub levels: Vec<Level>,
+    pub level_errors: Vec<String>,
     pub level_active: Option<usize>,
     pub planet_current_index: usize,
 
+    /// Puzzle of the day, played instead of `levels` while active
+    pub daily_level: Level,
+    pub daily_day: u64,
+    /// Best score on today's puzzle. Kept for the session only, with `is_daily_solved`, as
+    /// nothing is saved between runs
+    pub daily_best_score: Option<i32>,
+    pub is_daily_active: bool,
+    pub is_daily_solved: bool,
+
     pub score: i32,
 
     pub sim_step: usize,
     pub sim_step_computed: usize,
+    /// When the last step was computed, to give planets time to move before the next one
+    pub sim_step_time: f64,
 
     pub texture_explosion_01: Texture2D,
     pub texture_background_01: Texture2D,
+    pub texture_padlock_locked: Texture2D,
 
     pub sfx_hover_01: Sound,
     pub sfx_planet_place_01: Sound,
@@ -37,6 +63,9 @@ pub struct GameState {
     pub sfx_planet_remove_deny_01: Sound,
     pub sfx_explosion_01: Sound,
     pub sfx_level_start_01: Sound,
+    pub sfx_push_01: Sound,
+    pub sfx_absorb_01: Sound,
+    pub sfx_merge_01: Sound,
 
     pub music_level_end_01: Sound,
 }
@@ -49,21 +78,30 @@ impl GameState {
 
         let mouse_pos = f32::Vec2::ZERO;
         let is_mouse_in_grid = false;
-        let tile_highlighted_prev = IVec2::splat(-1);
-        let tile_highlighted = IVec2::ZERO;
+        let tile_highlighted_prev = Tile::new(-1, -1);
+        let tile_highlighted = Tile::ZERO;
+        let is_gravity_overlay_visible = false;
 
-        let levels = GameState::create_levels(&styles);
-        let level_active = Some(0);
+        let (levels, level_errors) = GameState::load_levels(&styles).await;
+        let level_active = if levels.is_empty() { None } else { Some(0) };
         // let level_active = Some(levels.len() - 1);
         let planet_current_index = 0;
 
+        let daily_day = daily::day_from_timestamp(date::now());
+        let daily_level = GameState::create_daily_level(daily_day, &styles);
+        let daily_best_score = None;
+        let is_daily_active = false;
+        let is_daily_solved = false;
+
         let score = 0;
 
         let sim_step = 0;
         let sim_step_computed = 0;
+        let sim_step_time = 0.0;
 
         let texture_explosion_01 = load_texture("assets/explosion_01.png").await.unwrap();
         let texture_background_01 = load_texture("assets/background.png").await.unwrap();
+        let texture_padlock_locked = load_texture("assets/padlock_locked.png").await.unwrap();
 
         let sfx_hover_01 = load_sound("assets/sfx/hover_02.ogg").await.unwrap();
         let sfx_planet_place_01 = load_sound("assets/sfx/planet_place_01.ogg").await.unwrap();
@@ -76,6 +114,9 @@ impl GameState {
             .unwrap();
         let sfx_explosion_01 = load_sound("assets/sfx/explosion_01.ogg").await.unwrap();
         let sfx_level_start_01 = load_sound("assets/sfx/level_start_01.ogg").await.unwrap();
+        let sfx_push_01 = load_sound("assets/sfx/push_01.ogg").await.unwrap();
+        let sfx_absorb_01 = load_sound("assets/sfx/absorb_01.ogg").await.unwrap();
+        let sfx_merge_01 = load_sound("assets/sfx/merge_01.ogg").await.unwrap();
 
         let music_level_end_01 = load_sound("assets/music/planet_001_short.ogg")
             .await
@@ -88,18 +129,28 @@ impl GameState {
             is_mouse_in_grid,
             tile_highlighted_prev,
             tile_highlighted,
+            is_gravity_overlay_visible,
 
             level_active,
             levels,
+            level_errors,
             planet_current_index,
 
+            daily_level,
+            daily_day,
+            daily_best_score,
+            is_daily_active,
+            is_daily_solved,
+
             score,
 
             sim_step,
             sim_step_computed,
+            sim_step_time,
 
             texture_explosion_01,
             texture_background_01,
+            texture_padlock_locked,
 
             sfx_hover_01,
             sfx_planet_place_01,
@@ -108,12 +159,19 @@ impl GameState {
             sfx_planet_remove_deny_01,
             sfx_explosion_01,
             sfx_level_start_01,
+            sfx_push_01,
+            sfx_absorb_01,
+            sfx_merge_01,
 
             music_level_end_01,
         }
     }
 
     pub fn current_level_mut(&mut self) -> Option<&mut Level> {
+        if self.is_daily_active {
+            return Some(&mut self.daily_level);
+        }
+
         match self.level_active {
             None => return None,
             Some(i) => return Some(&mut self.levels[i]),
@@ -121,12 +179,20 @@ impl GameState {
     }
 
     pub fn current_level(&self) -> Option<&Level> {
+        if self.is_daily_active {
+            return Some(&self.daily_level);
+        }
+
         match self.level_active {
             None => return None,
             Some(i) => return Some(&self.levels[i]),
         }
     }
 
+    pub fn create_daily_level(day: u64, styles: &Styles) -> Level {
+        Level::from_def(&daily::level(day), styles)
+    }
+
     fn show_loading_screen(styles: &Styles) {
         clear_background(styles.colors.black_1);
         let font_size = 16.0;
@@ -156,470 +222,201 @@ impl GameState {
         );
     }
 
-    pub fn create_levels(styles: &Styles) -> Vec<Level> {
-        use crate::planet::PlanetState::*;
-
-        let levels = vec![
-            Level::new(
-                "1. Planet",
-                IVec2::new(3, 3),
-                vec![Planet::new(
-                    0b0000,
-                    Pending,
-                    true,
-                    8.0,
-                    styles.colors.blue_1,
-                )],
-            ),
-            Level::new(
-                "2. Gravity",
-                IVec2::new(3, 3),
-                vec![
-                    Planet::new(0b0000, Pending, true, 9.0, styles.colors.yellow_1),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 0)),
-                        false,
-                        8.0,
-                        styles.colors.white,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 1)),
-                        false,
-                        8.0,
-                        styles.colors.red_light,
-                    ),
-                ],
-            ),
-            Level::new(
-                "3. Direction",
-                IVec2::new(3, 3),
-                vec![
-                    Planet::new(0b0000, Pending, true, 9.0, styles.colors.red_light),
-                    Planet::new(
-                        0b0010,
-                        Placed(IVec2::new(0, 0)),
-                        false,
-                        8.0,
-                        styles.colors.white,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 1)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_2,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 2)),
-                        false,
-                        8.0,
-                        styles.colors.blue_2,
-                    ),
-                ],
-            ),
-            Level::new(
-                "4. Two planets",
-                IVec2::new(3, 3),
-                vec![
-                    Planet::new(0b0000, Pending, true, 9.0, styles.colors.blue_4),
-                    Planet::new(0b0000, Pending, true, 7.0, styles.colors.white),
-                    Planet::new(
-                        0b0011,
-                        Placed(IVec2::new(0, 1)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_2,
-                    ),
-                    Planet::new(
-                        0b0011,
-                        Placed(IVec2::new(0, 2)),
-                        false,
-                        8.0,
-                        styles.colors.blue_3,
-                    ),
-                ],
-            ),
-            Level::new(
-                "5. Three planets",
-                IVec2::new(4, 4),
-                vec![
-                    Planet::new(0b0000, Pending, true, 9.0, styles.colors.blue_1),
-                    Planet::new(0b0001, Pending, true, 8.0, styles.colors.yellow_4),
-                    Planet::new(0b0000, Pending, true, 7.0, styles.colors.blue_4),
-                    Planet::new(
-                        0b0011,
-                        Placed(IVec2::new(0, 0)),
-                        false,
-                        7.0,
-                        styles.colors.yellow_2,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 2)),
-                        false,
-                        8.0,
-                        styles.colors.white,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 3)),
-                        false,
-                        9.0,
-                        styles.colors.white,
-                    ),
-                ],
-            ),
-            Level::new(
-                "6. Test",
-                IVec2::new(4, 4),
-                vec![
-                    Planet::new(0b0001, Pending, true, 8.0, styles.colors.yellow_4),
-                    Planet::new(0b0011, Pending, true, 9.0, styles.colors.red_light),
-                    Planet::new(0b0000, Pending, true, 7.0, styles.colors.white),
-                    Planet::new(
-                        0b0000,
-                        Placed(IVec2::new(0, 0)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_2,
-                    ),
-                    Planet::new(
-                        0b0000,
-                        Placed(IVec2::new(0, 2)),
-                        false,
-                        8.0,
-                        styles.colors.white,
-                    ),
-                    Planet::new(
-                        0b0000,
-                        Placed(IVec2::new(0, 3)),
-                        false,
-                        9.0,
-                        styles.colors.white,
-                    ),
-                ],
-            ),
-            Level::new(
-                "7. Obstacle",
-                IVec2::new(10, 2),
-                vec![
-                    Planet::new(0b0000, Pending, true, 7.0, styles.colors.white),
-                    Planet::new(0b0010, Pending, true, 9.0, styles.colors.red_light),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 0)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 1)),
-                        false,
-                        8.0,
-                        styles.colors.white,
-                    ),
-                ],
-            ),
-            Level::new(
-                "8. Swap",
-                IVec2::new(6, 2),
-                vec![
-                    Planet::new(0b0000, Pending, true, 7.0, styles.colors.white),
-                    Planet::new(0b0010, Pending, true, 9.0, styles.colors.blue_1),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 0)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_2,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 1)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                ],
-            ),
-            Level::new(
-                "9. Squeeze",
-                IVec2::new(5, 5),
-                vec![
-                    Planet::new(0b1111, Pending, true, 9.0, styles.colors.white),
-                    Planet::new(
-                        0b0000,
-                        Placed(IVec2::new(2, 0)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b1000,
-                        Placed(IVec2::new(1, 1)),
-                        false,
-                        8.0,
-                        styles.colors.blue_2,
-                    ),
-                    Planet::new(
-                        0b0000,
-                        Placed(IVec2::new(4, 2)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_4,
-                    ),
-                    Planet::new(
-                        0b0000,
-                        Placed(IVec2::new(0, 2)),
-                        false,
-                        9.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b0010,
-                        Placed(IVec2::new(3, 3)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b0000,
-                        Placed(IVec2::new(2, 4)),
-                        false,
-                        9.0,
-                        styles.colors.yellow_2,
-                    ),
-                ],
-            ),
-            Level::new(
-                "10. Intervention",
-                IVec2::new(5, 5),
-                vec![
-                    Planet::new(0b0000, Pending, true, 9.0, styles.colors.white),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(2, 0)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b1000,
-                        Placed(IVec2::new(1, 1)),
-                        true,
-                        8.0,
-                        styles.colors.white,
-                    ),
-                    Planet::new(
-                        0b1000,
-                        Placed(IVec2::new(4, 2)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b0100,
-                        Placed(IVec2::new(0, 2)),
-                        false,
-                        8.0,
-                        styles.colors.blue_1,
-                    ),
-                    Planet::new(
-                        0b0010,
-                        Placed(IVec2::new(3, 3)),
-                        true,
-                        9.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b1000,
-                        Placed(IVec2::new(2, 4)),
-                        false,
-                        7.0,
-                        styles.colors.yellow_3,
-                    ),
-                ],
-            ),
-            Level::new(
-                "11. Control",
-                IVec2::new(9, 4),
-                vec![
-                    Planet::new(0b1111, Pending, true, 9.0, styles.colors.white),
-                    Planet::new(0b1111, Pending, true, 9.0, styles.colors.white),
-                    Planet::new(
-                        0b1000,
-                        Placed(IVec2::new(4, 0)),
-                        true,
-                        8.0,
-                        styles.colors.yellow_2,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(4, 1)),
-                        true,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b0010,
-                        Placed(IVec2::new(4, 2)),
-                        true,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b0100,
-                        Placed(IVec2::new(4, 3)),
-                        true,
-                        9.0,
-                        styles.colors.blue_2,
-                    ),
-                ],
-            ),
-            Level::new(
-                "12. Defuse",
-                IVec2::new(9, 9),
-                vec![
-                    Planet::new(0b0100, Pending, true, 9.0, styles.colors.white),
-                    Planet::new(0b0010, Pending, true, 9.0, styles.colors.white),
-                    Planet::new(
-                        0b1111,
-                        Placed(IVec2::new(0, 0)),
-                        false,
-                        9.0,
-                        styles.colors.blue_2,
-                    ),
-                    Planet::new(
-                        0b1111,
-                        Placed(IVec2::new(0, 8)),
-                        false,
-                        8.0,
-                        styles.colors.blue_1,
-                    ),
-                    Planet::new(
-                        0b1111,
-                        Placed(IVec2::new(8, 0)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b1111,
-                        Placed(IVec2::new(8, 8)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                ],
-            ),
-            Level::new(
-                "13. Loop",
-                IVec2::new(7, 4),
-                vec![
-                    Planet::new(0b0100, Pending, true, 9.0, styles.colors.white),
-                    Planet::new(0b0010, Pending, true, 9.0, styles.colors.white),
-                    Planet::new(0b1000, Pending, true, 9.0, styles.colors.white),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 0)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_2,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 1)),
-                        false,
-                        8.0,
-                        styles.colors.yellow_1,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 2)),
-                        false,
-                        7.0,
-                        styles.colors.white,
-                    ),
-                    Planet::new(
-                        0b0001,
-                        Placed(IVec2::new(0, 3)),
-                        false,
-                        8.0,
-                        styles.colors.blue_2,
-                    ),
-                ],
-            ),
-            Level::new(
-                "14. Seven planets",
-                IVec2::new(3, 4),
-                vec![
-                    Planet::new(0b1000, Pending, true, 8.0, styles.colors.yellow_4),
-                    Planet::new(0b0001, Pending, true, 8.0, styles.colors.blue_2),
-                    Planet::new(0b0100, Pending, true, 9.0, styles.colors.white),
-                    Planet::new(0b0000, Pending, true, 7.0, styles.colors.yellow_3),
-                    Planet::new(0b0000, Pending, true, 8.0, styles.colors.white),
-                    Planet::new(0b0111, Pending, true, 9.0, styles.colors.blue_2),
-                    Planet::new(0b0011, Pending, true, 7.0, styles.colors.blue_1),
-                ],
-            ),
-        ];
-
-        levels
+    /// Loads every level listed in the level index. Levels that fail to load are skipped and
+    /// their errors returned instead.
+    pub async fn load_levels(styles: &Styles) -> (Vec<Level>, Vec<String>) {
+        let mut levels = Vec::new();
+        let mut errors = Vec::new();
+
+        let index_path = format!("{}/{}", LEVELS_DIR, level_file::INDEX_FILE);
+        let index = match load_string(&index_path).await {
+            Ok(index) => index,
+            Err(error) => {
+                errors.push(format!("{}: {}", index_path, error));
+                return (levels, errors);
+            }
+        };
+
+        for file in level_file::parse_index(&index) {
+            let path = format!("{}/{}", LEVELS_DIR, file);
+            let source = match load_string(&path).await {
+                Ok(source) => source,
+                Err(error) => {
+                    errors.push(format!("{}: {}", path, error));
+                    continue;
+                }
+            };
+
+            match level_file::parse_level(&path, &source) {
+                Ok(level_def) => levels.push(Level::from_def(&level_def, styles)),
+                Err(error) => errors.push(error.to_string()),
+            }
+        }
+
+        for error in &errors {
+            error!("{}", error);
+        }
+
+        (levels, errors)
     }
 }
 
+/// A point in a level's undo history
+#[derive(Clone)]
+pub struct Snapshot {
+    pub board: Board,
+    pub is_oscillating: bool,
+    pub is_lost: bool,
+    pub step_count: u32,
+    /// Steps of the timeline kept when going back to this point
+    pub timeline_len: usize,
+    /// Steps after those, dropped when leaving this point and put back on return
+    pub timeline_steps: Vec<Snapshot>,
+}
+
 #[derive(Clone)]
 pub struct Level {
-    pub name: &'static str,
+    pub name: String,
     pub planets: Vec<Planet>,
     pub planets_original: Vec<Planet>,
-    pub grid_tiles: IVec2,
+    pub grid_tiles: Tile,
+    pub rules: RuleSet,
 
     pub score: i32,
 
+    /// Next action suggested by the solver, cleared once the player acts
+    pub hint: Option<Action>,
+    pub hint_count: u32,
+    pub is_hint_unavailable: bool,
+    /// Search for the next hint, run a few boards every frame. Shared, so cloning the game state
+    /// doesn't copy every board visited so far
+    pub hint_search: Option<Rc<RefCell<Solver>>>,
+
+    /// Before each place or remove, most recent last
+    pub history: Vec<Snapshot>,
+    /// Undone since the last place or remove, most recent last
+    pub future: Vec<Snapshot>,
+
+    /// Steps triggered by the last place or remove
+    pub sim_run: Run,
+    /// Simulation steps run since the level started
+    pub step_count: u32,
+    /// Board at the start and after every simulation step of this attempt, oldest first
+    pub timeline: Vec<Snapshot>,
+    /// Step of the timeline being viewed, `None` while playing
+    pub timeline_view: Option<usize>,
+    /// Board to go back to once done viewing the timeline
+    pub timeline_live: Option<Snapshot>,
+    /// A planet moved since the last step, with [`RuleSet::is_manual`]
+    pub needs_step: bool,
+
     pub was_failed: bool,
     pub was_stable: bool,
+    pub was_oscillating: bool,
 
     pub is_failed: bool,
     pub is_stable: bool,
+    /// The last move left the planets cycling forever
+    pub is_oscillating: bool,
+    /// A planet left the grid while settling, which fails the level
+    pub is_lost: bool,
 
     pub is_setup: bool,
 }
 
 impl Level {
-    pub fn new(name: &'static str, grid_tiles: IVec2, planets: Vec<Planet>) -> Self {
+    pub fn new(name: &str, grid_tiles: Tile, rules: RuleSet, planets: Vec<Planet>) -> Self {
         let was_failed = false;
         let was_stable = false;
+        let was_oscillating = false;
 
         let is_failed = false;
         let is_stable = false;
+        let is_oscillating = false;
+        let is_lost = false;
 
         let is_setup = false;
 
         let score = 0;
 
+        let hint = None;
+        let hint_count = 0;
+        let is_hint_unavailable = false;
+        let hint_search = None;
+
+        let history = Vec::new();
+        let future = Vec::new();
+
+        let sim_run = Run::default();
+        let step_count = 0;
+        let needs_step = false;
+
+        let timeline = Vec::new();
+        let timeline_view = None;
+        let timeline_live = None;
+
         let planets_original = planets.clone();
 
-        Self {
-            name,
+        let mut level = Self {
+            name: name.to_string(),
             planets,
             planets_original,
             grid_tiles,
+            rules,
 
             score,
 
+            hint,
+            hint_count,
+            is_hint_unavailable,
+            hint_search,
+
+            history,
+            future,
+
+            sim_run,
+            step_count,
+            needs_step,
+
+            timeline,
+            timeline_view,
+            timeline_live,
+
             was_failed,
             was_stable,
+            was_oscillating,
 
             is_failed,
             is_stable,
+            is_oscillating,
+            is_lost,
 
             is_setup,
-        }
+        };
+        level.timeline.push(level.snapshot());
+
+        level
+    }
+
+    pub fn from_def(level_def: &LevelDef, styles: &Styles) -> Self {
+        let planets = level_def
+            .planets
+            .iter()
+            .map(|planet_def| {
+                let color = styles
+                    .colors
+                    .by_name(&planet_def.color)
+                    .unwrap_or(styles.colors.white);
+                Planet::new(planet_def.body, planet_def.size, color)
+            })
+            .collect();
+
+        Level::new(
+            &level_def.name,
+            level_def.grid_tiles,
+            level_def.rules,
+            planets,
+        )
     }
 
     pub fn grid_size_px(&self) -> f32::Vec2 {
@@ -638,17 +435,190 @@ impl Level {
         )
     }
 
+    /// Screen position of the centre of `tile`
+    pub fn tile_center(&self, tile: Tile) -> f32::Vec2 {
+        let grid_offset = self.grid_offset();
+
+        f32::Vec2::new(
+            tile.x as f32 * TILE_SIZE_X + grid_offset.x + TILE_SIZE_X / 2.0,
+            tile.y as f32 * TILE_SIZE_Y + grid_offset.y + TILE_SIZE_Y / 2.0,
+        )
+    }
+
+    pub fn board(&self) -> Board {
+        let bodies = self.planets.iter().map(|planet| planet.body).collect();
+        let mut board = Board::new(self.grid_tiles, bodies);
+        board.rules = self.rules;
+        board.needs_step = self.needs_step;
+
+        board
+    }
+
+    pub fn apply_board(&mut self, board: &Board) {
+        for (planet, body) in self.planets.iter_mut().zip(&board.bodies) {
+            planet.body = *body;
+        }
+        self.needs_step = board.needs_step;
+    }
+
     pub fn reset(&mut self) {
         self.planets = self.planets_original.clone();
 
         self.was_failed = false;
         self.was_stable = false;
+        self.was_oscillating = false;
 
         self.is_failed = false;
         self.is_stable = false;
+        self.is_oscillating = false;
+        self.is_lost = false;
 
         self.is_setup = false;
 
         self.score = 0;
+
+        self.hint = None;
+        self.hint_count = 0;
+        self.is_hint_unavailable = false;
+        self.hint_search = None;
+
+        self.history.clear();
+        self.future.clear();
+
+        self.sim_run = Run::default();
+        self.step_count = 0;
+        self.needs_step = false;
+
+        self.timeline = vec![self.snapshot()];
+        self.timeline_view = None;
+        self.timeline_live = None;
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: self.board(),
+            is_oscillating: self.is_oscillating,
+            is_lost: self.is_lost,
+            step_count: self.step_count,
+            timeline_len: self.timeline.len(),
+            timeline_steps: Vec::new(),
+        }
+    }
+
+    /// Goes back to the board before the last place or remove. Moves are not refunded and each
+    /// undo costs `UNDO_SCORE_COST` on top. Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let snapshot = match self.history.pop() {
+            None => return false,
+            Some(snapshot) => snapshot,
+        };
+
+        // The undone steps leave the timeline until redone
+        let mut current = self.snapshot();
+        current.timeline_steps = self
+            .timeline
+            .split_off(snapshot.timeline_len.min(self.timeline.len()));
+        current.timeline_len = snapshot.timeline_len;
+        self.future.push(current);
+        self.restore(&snapshot);
+        self.restore_timeline(&snapshot);
+        self.score -= UNDO_SCORE_COST;
+
+        true
+    }
+
+    /// Replays the last undone move. Free, as the move was already paid for. Returns `false` if
+    /// there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let snapshot = match self.future.pop() {
+            None => return false,
+            Some(snapshot) => snapshot,
+        };
+
+        self.history.push(self.snapshot());
+        self.restore(&snapshot);
+        self.restore_timeline(&snapshot);
+
+        true
+    }
+
+    /// Shows the board at `index` of the timeline, keeping the board being played to go back to.
+    pub fn view_timeline(&mut self, index: usize) {
+        let index = index.min(self.timeline.len() - 1);
+        if self.timeline_live.is_none() {
+            self.timeline_live = Some(self.snapshot());
+        }
+
+        self.timeline_view = Some(index);
+        let board = self.timeline[index].board.clone();
+        self.show(&board);
+    }
+
+    /// Goes back to the board being played before viewing the timeline.
+    pub fn stop_viewing_timeline(&mut self) {
+        if let Some(live) = self.timeline_live.take() {
+            self.show(&live.board);
+        }
+        self.timeline_view = None;
+    }
+
+    /// Plays on from the viewed step of the timeline, dropping the steps after it. Costs
+    /// `UNDO_SCORE_COST` like an undo, and can be undone. Returns `false` if the timeline isn't
+    /// being viewed.
+    pub fn resume_timeline(&mut self) -> bool {
+        let (index, live) = match (self.timeline_view.take(), self.timeline_live.take()) {
+            (Some(index), Some(live)) => (index, live),
+            _ => return false,
+        };
+
+        // Undoing the resume puts the dropped steps back
+        let mut live = live;
+        live.timeline_steps = self.timeline.split_off(index + 1);
+        live.timeline_len = index + 1;
+        self.history.push(live);
+        self.future.clear();
+
+        let snapshot = self.timeline[index].clone();
+        self.restore(&snapshot);
+        self.score -= UNDO_SCORE_COST;
+
+        true
+    }
+
+    fn restore_timeline(&mut self, snapshot: &Snapshot) {
+        self.timeline.truncate(snapshot.timeline_len);
+        self.timeline
+            .extend(snapshot.timeline_steps.iter().cloned());
+    }
+
+    fn show(&mut self, board: &Board) {
+        self.apply_board(board);
+
+        // Pending planets follow the mouse again, placed ones animate to their restored tile
+        for planet in &mut self.planets {
+            if planet.body.state == PlanetState::Pending {
+                planet.remove();
+            }
+        }
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) {
+        let board = &snapshot.board;
+        self.show(board);
+
+        // A restored collision has already exploded once
+        self.is_lost = snapshot.is_lost;
+        self.is_failed = board.is_failed() || self.is_lost;
+        self.was_failed = self.is_failed;
+        self.is_oscillating = snapshot.is_oscillating;
+        self.was_oscillating = self.is_oscillating;
+        self.step_count = snapshot.step_count;
+
+        self.is_stable = false;
+        self.was_stable = false;
+
+        self.hint = None;
+        self.is_hint_unavailable = false;
+        self.hint_search = None;
     }
 }
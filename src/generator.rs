@@ -0,0 +1,322 @@
+//! Seeded level generator. Candidates are checked with the solver and rated by how long their
+//! shortest solution is and how many choices the player has along the way.
+
+use crate::{
+    level_file::{LevelDef, PlanetDef},
+    rules::RuleSet,
+    sim::{Body, PlanetState, Tile},
+    solver::{self, Action, Limits, Outcome, Search},
+};
+
+/// Candidates tried before settling for the closest rating
+const MAX_ATTEMPTS: usize = 400;
+
+const COLOR_NAMES: [&str; 8] = [
+    "white",
+    "yellow_1",
+    "yellow_2",
+    "yellow_3",
+    "yellow_4",
+    "blue_1",
+    "blue_2",
+    "red_light",
+];
+
+/// Small deterministic PRNG (SplitMix64), so a seed gives the same level on every platform.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `min..=max`
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        let span = (max - min + 1) as u64;
+        min + (self.next_u64() % span) as i32
+    }
+
+    pub fn chance(&mut self, probability: f32) -> bool {
+        (self.next_u64() % 1000) < (probability * 1000.0) as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    fn params(&self) -> Params {
+        match self {
+            Difficulty::Easy => Params {
+                grid: (3, 4),
+                fixed: (1, 3),
+                pending: (2, 2),
+                rating: (4.0, 10.0),
+            },
+            Difficulty::Medium => Params {
+                grid: (4, 5),
+                fixed: (2, 4),
+                pending: (2, 3),
+                rating: (10.0, 18.0),
+            },
+            Difficulty::Hard => Params {
+                grid: (4, 6),
+                fixed: (3, 5),
+                pending: (3, 4),
+                rating: (18.0, 40.0),
+            },
+        }
+    }
+}
+
+/// Ranges, all inclusive, a difficulty draws its candidates from
+struct Params {
+    grid: (i32, i32),
+    fixed: (i32, i32),
+    pending: (i32, i32),
+    rating: (f32, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub solution_length: usize,
+    pub branching: f32,
+    /// Grows with the solution length and, more slowly, with the branching
+    pub score: f32,
+}
+
+impl Rating {
+    pub fn new(solution_length: usize, branching: f32) -> Self {
+        let score = solution_length as f32 * (1.0 + branching.max(1.0).log2());
+
+        Self {
+            solution_length,
+            branching,
+            score,
+        }
+    }
+
+    /// Rates a search, `None` if it found no solution.
+    pub fn from_search(search: &Search) -> Option<Self> {
+        match &search.outcome {
+            Outcome::Solved(actions) => Some(Rating::new(actions.len(), search.branching)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generated {
+    pub level_def: LevelDef,
+    pub rating: Rating,
+    pub solution: Vec<Action>,
+}
+
+/// Generates a solvable level needing at least two actions. The same seed and difficulty always
+/// give the same level.
+pub fn generate(seed: u64, difficulty: Difficulty) -> Generated {
+    let params = difficulty.params();
+    let target = (params.rating.0 + params.rating.1) / 2.0;
+    let limits = Limits {
+        max_depth: 10,
+        max_states: 50_000,
+    };
+
+    let mut rng = Rng::new(seed);
+    let mut best: Option<Generated> = None;
+
+    for _ in 0..MAX_ATTEMPTS {
+        let level_def = candidate(&mut rng, &params);
+        let search = solver::solve(&level_def.board(), limits);
+
+        let solution = match search.outcome {
+            Outcome::Solved(ref actions) if actions.len() >= 2 => actions.clone(),
+            _ => continue,
+        };
+        let rating = Rating::new(solution.len(), search.branching);
+
+        let generated = Generated {
+            level_def,
+            rating,
+            solution,
+        };
+        if rating.score >= params.rating.0 && rating.score <= params.rating.1 {
+            return generated;
+        }
+
+        let is_closer = match &best {
+            None => true,
+            Some(best) => (rating.score - target).abs() < (best.rating.score - target).abs(),
+        };
+        if is_closer {
+            best = Some(generated);
+        }
+    }
+
+    // Hard to miss with this many attempts, fall back to two planets just in case
+    best.unwrap_or_else(|| fallback(&mut rng))
+}
+
+/// Two planets without gravity, placed anywhere apart.
+fn fallback(rng: &mut Rng) -> Generated {
+    let planets = (0..2)
+        .map(|_| planet(rng, 0b0000, PlanetState::Pending, true))
+        .collect();
+
+    Generated {
+        level_def: LevelDef {
+            name: String::new(),
+            grid_tiles: Tile::new(3, 3),
+            rules: RuleSet::default(),
+            planets,
+        },
+        rating: Rating::new(2, 8.5),
+        solution: vec![
+            Action::Place {
+                planet: 0,
+                tile: Tile::ZERO,
+            },
+            Action::Place {
+                planet: 1,
+                tile: Tile::new(1, 0),
+            },
+        ],
+    }
+}
+
+fn candidate(rng: &mut Rng, params: &Params) -> LevelDef {
+    let grid_tiles = Tile::new(
+        rng.range(params.grid.0, params.grid.1),
+        rng.range(params.grid.0, params.grid.1),
+    );
+    let fixed_count = rng.range(params.fixed.0, params.fixed.1);
+    let pending_count = rng.range(params.pending.0, params.pending.1);
+
+    let mut planets = Vec::new();
+    for _ in 0..pending_count {
+        let gravity_field = random_gravity(rng);
+        planets.push(planet(rng, gravity_field, PlanetState::Pending, true));
+    }
+
+    let mut tiles: Vec<Tile> = Vec::new();
+    while (tiles.len() as i32) < fixed_count {
+        let tile = Tile::new(
+            rng.range(0, grid_tiles.x - 1),
+            rng.range(0, grid_tiles.y - 1),
+        );
+        if !tiles.contains(&tile) {
+            tiles.push(tile);
+        }
+    }
+
+    for tile in tiles {
+        let gravity_field = random_gravity(rng);
+        let is_removable = rng.chance(0.2);
+        planets.push(planet(
+            rng,
+            gravity_field,
+            PlanetState::Placed(tile),
+            is_removable,
+        ));
+    }
+
+    LevelDef {
+        name: String::new(),
+        grid_tiles,
+        rules: RuleSet::default(),
+        planets,
+    }
+}
+
+/// Mostly single directions, which read better than busy fields
+fn random_gravity(rng: &mut Rng) -> u8 {
+    if rng.chance(0.2) {
+        0b0000
+    } else if rng.chance(0.7) {
+        1 << rng.range(0, 3)
+    } else {
+        rng.range(1, 0b1111) as u8
+    }
+}
+
+fn planet(rng: &mut Rng, gravity_field: u8, state: PlanetState, is_removable: bool) -> PlanetDef {
+    let color = COLOR_NAMES[rng.range(0, COLOR_NAMES.len() as i32 - 1) as usize];
+
+    PlanetDef {
+        body: Body::new(gravity_field, state, is_removable),
+        size: rng.range(7, 9) as f32,
+        color: color.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_the_same_level() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(generate(7, difficulty), generate(7, difficulty));
+        }
+        assert_ne!(
+            generate(7, Difficulty::Easy).level_def,
+            generate(8, Difficulty::Easy).level_def
+        );
+    }
+
+    #[test]
+    fn levels_are_solvable_within_their_rating() {
+        for difficulty in Difficulty::ALL {
+            let (min, max) = difficulty.params().rating;
+            for seed in 0..3 {
+                let generated = generate(seed, difficulty);
+                let search = solver::solve(&generated.level_def.board(), Limits::default());
+
+                let rating = Rating::from_search(&search).unwrap();
+                assert_eq!(rating.solution_length, generated.solution.len());
+                assert!(generated.solution.len() >= 2);
+                assert!(
+                    rating.score >= min && rating.score <= max,
+                    "{} seed {} rated {}",
+                    difficulty.name(),
+                    seed,
+                    rating.score
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fallback_needs_two_actions() {
+        let generated = fallback(&mut Rng::new(0));
+        let search = solver::solve(&generated.level_def.board(), Limits::default());
+
+        assert_eq!(search.outcome, Outcome::Solved(generated.solution.clone()));
+        assert_eq!(Rating::from_search(&search), Some(generated.rating));
+    }
+}
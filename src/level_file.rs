@@ -0,0 +1,721 @@
+//! Plain text level files, shipped under `assets/levels/`.
+//!
+//! `assets/levels/levels.txt` lists the level files in play order, one file name per line. Each
+//! level file looks like this:
+//!
+//! ```text
+//! # Lines starting with `#` are comments.
+//! name: 2. Gravity
+//! grid: 3x3
+//!
+//! # Planets, in the order they are shown on the stack.
+//! planet: gravity=0000 size=9 color=yellow_1
+//! planet: gravity=0001 removable=false size=8 color=white tile=0,0
+//! ```
+//!
+//! `name` and `grid` (columns x rows) are required. An optional `rules` line changes how the
+//! simulation plays out for the level, with the same `field=value` syntax as planets, e.g.
+//! `rules: reach=nearest steps=2`. Every field defaults to the rules of the first levels:
+//!
+//! - `reach`: `all` for gravity pulling every planet in range, or `nearest` for gravity stopping
+//!   at the first planet in each direction.
+//! - `clamp`: `true` to move planets at most one tile per axis each step, `false` to add up every
+//!   pull.
+//! - `priority`: `both`, `row` or `column`, the axis that wins when a planet is pulled along both.
+//! - `collision`: `explode` for planets colliding when they end on the same tile or meet on the
+//!   way, or `swap` to only collide on the same tile and let planets pass through each other.
+//!   `merge` and `xor` also let planets pass through each other, and merge the ones ending on the
+//!   same tile into a heavier planet with the gravity of both, or only the gravity one of them
+//!   had with `xor`.
+//! - `edge`: what planets pulled past the border do. `open` to keep drifting off the grid, `wall`
+//!   to stop at the border, `wrap` to come back in on the opposite side, `fall` to be lost and fail
+//!   the level, or `return` to go back to the stack.
+//! - `steps`: simulation steps run after every move, from 1 to 9, or `settle` to keep going until
+//!   planets stop moving, collide, leave the grid or start repeating themselves.
+//! - `manual`: `true` for moves not running the simulation, the player steps it instead.
+//!
+//! Every `planet` line takes these fields:
+//!
+//! - `kind`: `planet`, or `black_hole` for a planet absorbing the planets it meets instead of
+//!   colliding with them. Defaults to `planet`.
+//! - `gravity`: required, bits for up, down, left and right, e.g. `1001` pulls up and right. Eight
+//!   bits add the diagonals in front: up-left, up-right, down-left and down-right, e.g. `10000001`
+//!   pulls up-left and right.
+//! - `push`: bits of the fields that push planets away instead of pulling them, in the same order
+//!   as `gravity`, defaults to none. E.g. `gravity=1001 push=0001` pulls up and pushes right.
+//! - `range`: tiles each field reaches, defaults to the whole row, column or diagonal. Either one
+//!   range for every direction, e.g. `2`, one for up, down, left and right with `-` for
+//!   unlimited, e.g. `2,-,1,1`, or eight adding up-left, up-right, down-left and down-right.
+//! - `mass`: from 1 to 5, defaults to `1`. Planets are only pulled by planets at least as heavy,
+//!   and the heavier side wins when pulled both ways along a row or column.
+//! - `removable`: `true` or `false`, defaults to `true`.
+//! - `anchored`: `true` for a planet gravity can't move, defaults to `false`.
+//! - `size`: radius in pixels, defaults to `8`. Heavier planets are drawn bigger.
+//! - `color`: one of [`COLOR_NAMES`], defaults to `white`.
+//! - `tile`: `x,y` of a planet that starts placed on the grid. Planets without it start on the
+//!   stack, waiting for the player to place them.
+
+use std::{error::Error, fmt, fs, path::Path};
+
+use crate::{
+    rules::{Collision, Edge, Priority, Reach, RuleSet, Steps},
+    sim::{Board, Body, Kind, PlanetState, Tile, MASS_DEFAULT, MASS_MAX, RANGE_UNLIMITED},
+};
+
+pub const INDEX_FILE: &str = "levels.txt";
+
+/// Palette colours a planet can use, matching the fields of the game's `Colors`.
+pub const COLOR_NAMES: [&str; 16] = [
+    "grey_dark",
+    "grey_mid",
+    "grey_light",
+    "white",
+    "black_1",
+    "black_2",
+    "red_light",
+    "red_dark",
+    "yellow_1",
+    "yellow_2",
+    "yellow_3",
+    "yellow_4",
+    "blue_1",
+    "blue_2",
+    "blue_3",
+    "blue_4",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelDef {
+    pub name: String,
+    pub grid_tiles: Tile,
+    pub rules: RuleSet,
+    pub planets: Vec<PlanetDef>,
+}
+
+impl LevelDef {
+    pub fn board(&self) -> Board {
+        let bodies = self.planets.iter().map(|planet| planet.body).collect();
+        let mut board = Board::new(self.grid_tiles, bodies);
+        board.rules = self.rules;
+
+        board
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanetDef {
+    pub body: Body,
+    pub size: f32,
+    pub color: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub file: String,
+    /// 1-based, 0 when the error concerns the whole file
+    pub line: usize,
+    pub field: String,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(file: &str, line: usize, field: &str, message: impl Into<String>) -> Self {
+        Self {
+            file: file.to_string(),
+            line,
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.file, self.message)
+        } else {
+            write!(
+                f,
+                "{}:{}: {}: {}",
+                self.file, self.line, self.field, self.message
+            )
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Returns the level file names listed in an index file.
+pub fn parse_index(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .map(strip_comment)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn parse_level(file: &str, source: &str) -> Result<LevelDef, ParseError> {
+    let mut name: Option<String> = None;
+    let mut grid_tiles: Option<Tile> = None;
+    let mut rules: Option<RuleSet> = None;
+    let mut planets = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let line = strip_comment(line);
+        if line.is_empty() {
+            continue;
+        }
+
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => {
+                return Err(ParseError::new(
+                    file,
+                    line_number,
+                    line,
+                    "expected `key: value`",
+                ))
+            }
+        };
+
+        match key {
+            "name" => {
+                if name.is_some() {
+                    return Err(ParseError::new(file, line_number, key, "duplicated"));
+                }
+                if value.is_empty() {
+                    return Err(ParseError::new(file, line_number, key, "is empty"));
+                }
+                name = Some(value.to_string());
+            }
+            "grid" => {
+                if grid_tiles.is_some() {
+                    return Err(ParseError::new(file, line_number, key, "duplicated"));
+                }
+                grid_tiles = Some(parse_grid(file, line_number, value)?);
+            }
+            "rules" => {
+                if rules.is_some() {
+                    return Err(ParseError::new(file, line_number, key, "duplicated"));
+                }
+                rules = Some(parse_rules(file, line_number, value)?);
+            }
+            "planet" => planets.push(parse_planet(file, line_number, value)?),
+            _ => {
+                return Err(ParseError::new(
+                    file,
+                    line_number,
+                    key,
+                    "unknown key, expected `name`, `grid`, `rules` or `planet`",
+                ))
+            }
+        }
+    }
+
+    let name = name.ok_or_else(|| ParseError::new(file, 0, "name", "missing `name`"))?;
+    let grid_tiles =
+        grid_tiles.ok_or_else(|| ParseError::new(file, 0, "grid", "missing `grid`"))?;
+
+    Ok(LevelDef {
+        name,
+        grid_tiles,
+        rules: rules.unwrap_or_default(),
+        planets,
+    })
+}
+
+/// Parses the `key=value` fields of a level's rules, as found after `rules:`.
+pub fn parse_rules(file: &str, line: usize, fields: &str) -> Result<RuleSet, ParseError> {
+    let mut rules = RuleSet::default();
+    let mut keys: Vec<&str> = Vec::new();
+
+    for field in fields.split_whitespace() {
+        let (key, value) = match field.split_once('=') {
+            Some(pair) => pair,
+            None => return Err(ParseError::new(file, line, field, "expected `field=value`")),
+        };
+
+        match key {
+            "reach" => {
+                rules.reach = parse_choice(file, line, key, value, &Reach::ALL, Reach::name)?
+            }
+            "clamp" => rules.is_clamped = parse_bool(file, line, key, value)?,
+            "priority" => {
+                rules.priority =
+                    parse_choice(file, line, key, value, &Priority::ALL, Priority::name)?
+            }
+            "collision" => {
+                rules.collision =
+                    parse_choice(file, line, key, value, &Collision::ALL, Collision::name)?
+            }
+            "edge" => rules.edge = parse_choice(file, line, key, value, &Edge::ALL, Edge::name)?,
+            "steps" => rules.steps = parse_steps(file, line, key, value)?,
+            "manual" => rules.is_manual = parse_bool(file, line, key, value)?,
+            _ => return Err(ParseError::new(file, line, key, "unknown rule")),
+        }
+
+        if keys.contains(&key) {
+            return Err(ParseError::new(file, line, key, "duplicated"));
+        }
+        keys.push(key);
+    }
+
+    Ok(rules)
+}
+
+/// Value of a level's `rules` line, `None` if every rule is the default.
+pub fn format_rules(rules: &RuleSet) -> Option<String> {
+    let default = RuleSet::default();
+    let mut fields = Vec::new();
+
+    if rules.reach != default.reach {
+        fields.push(format!("reach={}", rules.reach.name()));
+    }
+    if rules.is_clamped != default.is_clamped {
+        fields.push(format!("clamp={}", rules.is_clamped));
+    }
+    if rules.priority != default.priority {
+        fields.push(format!("priority={}", rules.priority.name()));
+    }
+    if rules.collision != default.collision {
+        fields.push(format!("collision={}", rules.collision.name()));
+    }
+    if rules.edge != default.edge {
+        fields.push(format!("edge={}", rules.edge.name()));
+    }
+    match rules.steps {
+        _ if rules.steps == default.steps => {}
+        Steps::Count(count) => fields.push(format!("steps={}", count)),
+        Steps::Settle => fields.push("steps=settle".to_string()),
+    }
+    if rules.is_manual != default.is_manual {
+        fields.push(format!("manual={}", rules.is_manual));
+    }
+
+    if fields.is_empty() {
+        None
+    } else {
+        Some(fields.join(" "))
+    }
+}
+
+/// Parses the `key=value` fields of a planet, as found after `planet:`.
+pub fn parse_planet(file: &str, line: usize, fields: &str) -> Result<PlanetDef, ParseError> {
+    let mut kind: Option<Kind> = None;
+    let mut gravity_field: Option<u8> = None;
+    let mut gravity_range: Option<[u8; 8]> = None;
+    let mut push_field: Option<u8> = None;
+    let mut mass: Option<u8> = None;
+    let mut is_removable: Option<bool> = None;
+    let mut is_anchored: Option<bool> = None;
+    let mut size: Option<f32> = None;
+    let mut color: Option<String> = None;
+    let mut tile: Option<Tile> = None;
+
+    for field in fields.split_whitespace() {
+        let (key, value) = match field.split_once('=') {
+            Some(pair) => pair,
+            None => return Err(ParseError::new(file, line, field, "expected `field=value`")),
+        };
+
+        let is_set = match key {
+            "kind" => kind
+                .replace(parse_choice(
+                    file,
+                    line,
+                    key,
+                    value,
+                    &Kind::ALL,
+                    Kind::name,
+                )?)
+                .is_some(),
+            "gravity" => gravity_field
+                .replace(parse_gravity(file, line, key, value)?)
+                .is_some(),
+            "push" => push_field
+                .replace(parse_gravity(file, line, key, value)?)
+                .is_some(),
+            "range" => gravity_range
+                .replace(parse_range(file, line, key, value)?)
+                .is_some(),
+            "mass" => mass.replace(parse_mass(file, line, key, value)?).is_some(),
+            "removable" => is_removable
+                .replace(parse_bool(file, line, key, value)?)
+                .is_some(),
+            "anchored" => is_anchored
+                .replace(parse_bool(file, line, key, value)?)
+                .is_some(),
+            "size" => size.replace(parse_size(file, line, key, value)?).is_some(),
+            "color" => color
+                .replace(parse_color(file, line, key, value)?)
+                .is_some(),
+            "tile" => tile.replace(parse_tile(file, line, key, value)?).is_some(),
+            _ => return Err(ParseError::new(file, line, key, "unknown field")),
+        };
+
+        if is_set {
+            return Err(ParseError::new(file, line, key, "duplicated"));
+        }
+    }
+
+    let gravity_field =
+        gravity_field.ok_or_else(|| ParseError::new(file, line, "gravity", "missing"))?;
+    let push_field = push_field.unwrap_or(0);
+    if push_field & !gravity_field != 0 {
+        return Err(ParseError::new(
+            file,
+            line,
+            "push",
+            "pushes in a direction without gravity",
+        ));
+    }
+    let state = match tile {
+        Some(tile) => PlanetState::Placed(tile),
+        None => PlanetState::Pending,
+    };
+
+    let mut body = Body::new(gravity_field, state, is_removable.unwrap_or(true));
+    if let Some(gravity_range) = gravity_range {
+        body.gravity_range = gravity_range;
+    }
+    body.kind = kind.unwrap_or_default();
+    body.push_field = push_field;
+    body.is_anchored = is_anchored.unwrap_or(false);
+    body.mass = mass.unwrap_or(MASS_DEFAULT);
+
+    Ok(PlanetDef {
+        body,
+        size: size.unwrap_or(8.0),
+        color: color.unwrap_or_else(|| "white".to_string()),
+    })
+}
+
+/// Writes a level in the format read by [`parse_level`].
+pub fn format_level(level_def: &LevelDef) -> String {
+    let mut out = format!(
+        "name: {}\ngrid: {}x{}\n",
+        level_def.name, level_def.grid_tiles.x, level_def.grid_tiles.y
+    );
+    if let Some(rules) = format_rules(&level_def.rules) {
+        out.push_str(&format!("rules: {}\n", rules));
+    }
+    out.push('\n');
+
+    for planet in &level_def.planets {
+        out.push_str("planet:");
+        if planet.body.kind != Kind::default() {
+            out.push_str(&format!(" kind={}", planet.body.kind.name()));
+        }
+        out.push_str(&format!(
+            " gravity={}",
+            format_gravity(planet.body.gravity_field)
+        ));
+        if planet.body.push_field != 0 {
+            out.push_str(&format!(" push={}", format_gravity(planet.body.push_field)));
+        }
+        if let Some(range) = format_range(&planet.body) {
+            out.push_str(&format!(" range={}", range));
+        }
+        if planet.body.mass != MASS_DEFAULT {
+            out.push_str(&format!(" mass={}", planet.body.mass));
+        }
+        if !planet.body.is_removable {
+            out.push_str(" removable=false");
+        }
+        if planet.body.is_anchored {
+            out.push_str(" anchored=true");
+        }
+        out.push_str(&format!(" size={} color={}", planet.size, planet.color));
+        match planet.body.state {
+            PlanetState::Pending | PlanetState::Absorbed => {}
+            PlanetState::Placed(tile) | PlanetState::Colliding(tile) => {
+                out.push_str(&format!(" tile={},{}", tile.x, tile.y))
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Value of a planet's `gravity` field, with the diagonal bits only if any is set.
+pub fn format_gravity(gravity_field: u8) -> String {
+    if gravity_field > 0b1111 {
+        format!("{:08b}", gravity_field)
+    } else {
+        format!("{:04b}", gravity_field)
+    }
+}
+
+/// Value of a planet's `range` field, `None` if every field is unlimited.
+pub fn format_range(body: &Body) -> Option<String> {
+    let format_one = |range: u8| {
+        if range == RANGE_UNLIMITED {
+            "-".to_string()
+        } else {
+            range.to_string()
+        }
+    };
+
+    let [first, ..] = body.gravity_range;
+    let (straight, diagonal) = body.gravity_range.split_at(4);
+    if body.gravity_range.iter().all(|range| *range == first) {
+        if first == RANGE_UNLIMITED {
+            None
+        } else {
+            Some(format_one(first))
+        }
+    } else if diagonal.iter().all(|range| *range == RANGE_UNLIMITED) {
+        let ranges: Vec<String> = straight.iter().map(|r| format_one(*r)).collect();
+        Some(ranges.join(","))
+    } else {
+        let ranges: Vec<String> = body.gravity_range.iter().map(|r| format_one(*r)).collect();
+        Some(ranges.join(","))
+    }
+}
+
+/// Reads the index and every level it lists from a directory, for native tools.
+pub fn load_dir(dir: &Path) -> Result<Vec<LevelDef>, ParseError> {
+    let read = |file: &str| {
+        fs::read_to_string(dir.join(file))
+            .map_err(|error| ParseError::new(file, 0, "", error.to_string()))
+    };
+
+    parse_index(&read(INDEX_FILE)?)
+        .iter()
+        .map(|file| parse_level(file, &read(file)?))
+        .collect()
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.split_once('#') {
+        Some((content, _)) => content.trim(),
+        None => line.trim(),
+    }
+}
+
+fn parse_grid(file: &str, line: usize, value: &str) -> Result<Tile, ParseError> {
+    let parsed = value
+        .split_once('x')
+        .and_then(|(x, y)| Some(Tile::new(x.trim().parse().ok()?, y.trim().parse().ok()?)));
+
+    match parsed {
+        Some(tile) if tile.x > 0 && tile.y > 0 => Ok(tile),
+        _ => Err(ParseError::new(
+            file,
+            line,
+            "grid",
+            format!("expected `columns x rows`, found `{}`", value),
+        )),
+    }
+}
+
+fn parse_gravity(file: &str, line: usize, key: &str, value: &str) -> Result<u8, ParseError> {
+    if value.is_empty() || (value.len() > 4 && value.len() != 8) {
+        return Err(ParseError::new(
+            file,
+            line,
+            key,
+            format!(
+                "expected up to 4 bits, or 8 with diagonals, found `{}`",
+                value
+            ),
+        ));
+    }
+
+    u8::from_str_radix(value, 2).map_err(|_| {
+        ParseError::new(
+            file,
+            line,
+            key,
+            format!("expected binary digits, found `{}`", value),
+        )
+    })
+}
+
+fn parse_range(file: &str, line: usize, key: &str, value: &str) -> Result<[u8; 8], ParseError> {
+    let parse_one = |range: &str| match range {
+        "-" => Some(RANGE_UNLIMITED),
+        _ => range
+            .parse::<u8>()
+            .ok()
+            .filter(|range| *range > 0 && *range < RANGE_UNLIMITED),
+    };
+
+    let ranges: Option<Vec<u8>> = value.split(',').map(parse_one).collect();
+    match ranges.as_deref() {
+        Some(&[range]) => Ok([range; 8]),
+        // Diagonals reach the whole way
+        Some(&[up, down, left, right]) => {
+            let mut ranges = [RANGE_UNLIMITED; 8];
+            ranges[..4].copy_from_slice(&[up, down, left, right]);
+            Ok(ranges)
+        }
+        Some(&[up, down, left, right, up_left, up_right, down_left, down_right]) => Ok([
+            up, down, left, right, up_left, up_right, down_left, down_right,
+        ]),
+        _ => Err(ParseError::new(
+            file,
+            line,
+            key,
+            format!(
+                "expected one, four or eight ranges from 1 to {} or `-`, found `{}`",
+                RANGE_UNLIMITED - 1,
+                value
+            ),
+        )),
+    }
+}
+
+fn parse_mass(file: &str, line: usize, key: &str, value: &str) -> Result<u8, ParseError> {
+    value
+        .parse::<u8>()
+        .ok()
+        .filter(|mass| (1..=MASS_MAX).contains(mass))
+        .ok_or_else(|| {
+            ParseError::new(
+                file,
+                line,
+                key,
+                format!(
+                    "expected a number from 1 to {}, found `{}`",
+                    MASS_MAX, value
+                ),
+            )
+        })
+}
+
+/// Parses one of a rule's named values, e.g. `nearest` for `reach`.
+fn parse_choice<T: Copy>(
+    file: &str,
+    line: usize,
+    key: &str,
+    value: &str,
+    choices: &[T],
+    name: fn(&T) -> &'static str,
+) -> Result<T, ParseError> {
+    choices
+        .iter()
+        .find(|choice| name(choice) == value)
+        .copied()
+        .ok_or_else(|| {
+            let names: Vec<String> = choices
+                .iter()
+                .map(|choice| format!("`{}`", name(choice)))
+                .collect();
+            ParseError::new(
+                file,
+                line,
+                key,
+                format!("expected one of {}, found `{}`", names.join(", "), value),
+            )
+        })
+}
+
+fn parse_steps(file: &str, line: usize, key: &str, value: &str) -> Result<Steps, ParseError> {
+    match value.parse::<u8>() {
+        _ if value == "settle" => Ok(Steps::Settle),
+        Ok(count) if (1..=9).contains(&count) => Ok(Steps::Count(count)),
+        _ => Err(ParseError::new(
+            file,
+            line,
+            key,
+            format!(
+                "expected a number from 1 to 9 or `settle`, found `{}`",
+                value
+            ),
+        )),
+    }
+}
+
+fn parse_bool(file: &str, line: usize, key: &str, value: &str) -> Result<bool, ParseError> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(ParseError::new(
+            file,
+            line,
+            key,
+            format!("expected `true` or `false`, found `{}`", value),
+        )),
+    }
+}
+
+fn parse_size(file: &str, line: usize, key: &str, value: &str) -> Result<f32, ParseError> {
+    match value.parse::<f32>() {
+        Ok(size) if size > 0.0 => Ok(size),
+        _ => Err(ParseError::new(
+            file,
+            line,
+            key,
+            format!("expected a positive number, found `{}`", value),
+        )),
+    }
+}
+
+fn parse_color(file: &str, line: usize, key: &str, value: &str) -> Result<String, ParseError> {
+    if COLOR_NAMES.contains(&value) {
+        Ok(value.to_string())
+    } else {
+        Err(ParseError::new(
+            file,
+            line,
+            key,
+            format!("unknown palette colour `{}`", value),
+        ))
+    }
+}
+
+fn parse_tile(file: &str, line: usize, key: &str, value: &str) -> Result<Tile, ParseError> {
+    value
+        .split_once(',')
+        .and_then(|(x, y)| Some(Tile::new(x.trim().parse().ok()?, y.trim().parse().ok()?)))
+        .ok_or_else(|| {
+            ParseError::new(
+                file,
+                line,
+                key,
+                format!("expected `x,y`, found `{}`", value),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(source: &str) -> ParseError {
+        parse_level("test.level", source).expect_err("level should not parse")
+    }
+
+    #[test]
+    fn missing_name_is_reported_for_the_whole_file() {
+        let error = parse_error("grid: 3x3\nplanet: gravity=0000");
+        assert_eq!((error.line, error.field.as_str()), (0, "name"));
+    }
+
+    #[test]
+    fn invalid_gravity_is_reported_on_its_line() {
+        let error = parse_error("name: Test\ngrid: 3x3\n\nplanet: gravity=0201");
+        assert_eq!((error.line, error.field.as_str()), (4, "gravity"));
+    }
+
+    #[test]
+    fn unknown_color_is_reported_on_its_line() {
+        let error = parse_error("name: Test\ngrid: 3x3\nplanet: gravity=0000 color=mauve");
+        assert_eq!((error.line, error.field.as_str()), (3, "color"));
+    }
+
+    #[test]
+    fn duplicated_field_is_reported_on_its_second_line() {
+        let error = parse_error("name: Test\ngrid: 3x3\n# again\ngrid: 4x4");
+        assert_eq!((error.line, error.field.as_str()), (4, "grid"));
+        assert_eq!(error.message, "duplicated");
+
+        let error = parse_error("name: Test\ngrid: 3x3\nplanet: size=8 size=9");
+        assert_eq!((error.line, error.field.as_str()), (3, "size"));
+        assert_eq!(error.message, "duplicated");
+    }
+}
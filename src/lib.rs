@@ -0,0 +1,10 @@
+//! Game rules that don't depend on macroquad, shared by the game, tools and tests.
+
+pub mod ascii;
+pub mod daily;
+pub mod generator;
+pub mod level_file;
+pub mod rules;
+pub mod sim;
+pub mod solver;
+pub mod validate;
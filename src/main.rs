@@ -4,13 +4,20 @@ mod planet;
 mod styles;
 mod text;
 
+use std::{cell::RefCell, rc::Rc};
+
+use akj_21::{
+    daily,
+    rules::{Collision, Edge, Priority, Reach, RuleSet, Steps},
+    sim::{Board, Direction, Event, Halt, PlanetState, Run, Tile},
+    solver::{self, Action, Limits, Outcome, Solver},
+};
 use constants::*;
 use game_state::GameState;
 use macroquad::{
     audio::{play_sound, play_sound_once, stop_sound, PlaySoundParams},
     prelude::*,
 };
-use planet::PlanetState;
 use text::draw_scaled_text;
 
 #[macroquad::main("akj-21")]
@@ -23,23 +30,35 @@ async fn main() {
     loop {
         game_state.mouse_pos = camera.screen_to_world(f32::Vec2::from(mouse_position()));
 
+        update_daily(&mut game_state);
         update_next_level(&mut game_state);
         if setup_level(&mut game_state) {
             next_frame().await;
             continue;
         }
 
+        update_timeline(&mut game_state);
+        update_history(&mut game_state);
+        update_hint(&mut game_state);
+        update_gravity_overlay(&mut game_state);
         update_planets(&mut game_state);
+        update_simulate(&mut game_state);
         update_sim(&mut game_state);
         update_score(&mut game_state);
 
         render_background(&game_state);
         render_grid(&mut game_state);
+        render_preview(&game_state);
+        render_hint(&game_state);
         render_level_name(&game_state);
         render_planets(&mut game_state);
         render_level_failed(&game_state);
+        render_level_oscillating(&game_state);
         render_help(&game_state);
+        render_sim_controls(&game_state);
+        render_timeline(&game_state);
         render_score(&game_state);
+        render_level_errors(&game_state);
 
         update_win_condition(&mut game_state);
 
@@ -80,20 +99,81 @@ fn render_level_name(game_state: &GameState) {
         game_state.styles.colors.yellow_1,
     );
     draw_scaled_text(
-        format!("{}", level.name).as_str(),
+        &level.name,
         pos_message_x,
         pos_message_y + font_size / 1.333,
         font_size,
         &game_state.styles.colors.black_1,
     );
+
+    // Rules that differ from the first levels are spelled out under the name
+    let mut pos_note_y = pos_message_y + 16.0 + 14.0;
+    for note in rule_notes(&level.rules) {
+        draw_scaled_text(
+            &note,
+            pos_message_x,
+            pos_note_y,
+            12.0,
+            &game_state.styles.colors.yellow_2,
+        );
+        pos_note_y += 12.0;
+    }
+}
+
+fn rule_notes(rules: &RuleSet) -> Vec<String> {
+    let mut notes = Vec::new();
+
+    match rules.reach {
+        Reach::All => {}
+        Reach::Nearest => notes.push("Gravity stops at the first planet".to_string()),
+    }
+    if !rules.is_clamped {
+        notes.push("Pulls add up".to_string());
+    }
+    match rules.priority {
+        Priority::Both => {}
+        Priority::Row => notes.push("Row pulls win".to_string()),
+        Priority::Column => notes.push("Column pulls win".to_string()),
+    }
+    match rules.collision {
+        Collision::Explode => {}
+        Collision::Swap => notes.push("Planets can swap places".to_string()),
+        Collision::Merge => notes.push("Colliding planets merge".to_string()),
+        Collision::Xor => notes.push("Colliding planets merge, shared gravity cancels".to_string()),
+    }
+    match rules.edge {
+        Edge::Open => {}
+        Edge::Wall => notes.push("Walls stop planets at the border".to_string()),
+        Edge::Wrap => notes.push("Planets wrap around the border".to_string()),
+        Edge::Fall => notes.push("Planets falling off the grid are lost".to_string()),
+        Edge::Return => notes.push("Planets falling off go back to the stack".to_string()),
+    }
+    match rules.steps {
+        Steps::Count(1) => {}
+        Steps::Count(count) => notes.push(format!("{} steps per move", count)),
+        Steps::Settle => notes.push("Planets move until they settle".to_string()),
+    }
+    if rules.is_manual {
+        notes.push("Planets wait for you to simulate".to_string());
+    }
+
+    notes
 }
 
 fn render_score(game_state: &GameState) {
     let font_size = 12.0;
     let pos_message_x = 8.0;
-    let pos_message_y = SCREEN_H - font_size * 1.666;
+    let pos_message_y = SCREEN_H - font_size * 2.666;
+    let daily_status = match game_state.daily_best_score {
+        Some(best) if game_state.is_daily_solved => format!("solved, best {}", best),
+        _ => "not solved yet".to_string(),
+    };
     draw_scaled_text(
-        format!("Score: {}", game_state.score).as_str(),
+        format!(
+            "Score: {}  Daily puzzle: {}",
+            game_state.score, daily_status
+        )
+        .as_str(),
         pos_message_x,
         pos_message_y,
         font_size,
@@ -101,16 +181,72 @@ fn render_score(game_state: &GameState) {
     );
 }
 
+fn render_level_errors(game_state: &GameState) {
+    let font_size = 12.0;
+    let pos_message_x = 8.0;
+    let mut pos_message_y = 32.0;
+    for error in &game_state.level_errors {
+        draw_scaled_text(
+            error,
+            pos_message_x,
+            pos_message_y,
+            font_size,
+            &game_state.styles.colors.red_light,
+        );
+        pos_message_y += font_size;
+    }
+}
+
 fn render_help(game_state: &GameState) {
     let font_size = 12.0;
     let pos_message_x = 8.0;
-    let pos_message_y = SCREEN_H - font_size * 0.666;
+    let pos_message_y = SCREEN_H - font_size * 1.666;
+
+    // Two lines, one doesn't fit the width of the screen
+    let lines = [
+        "<Z> undo (-1)  <Y> redo  <R> retry  <H> hint (-5)",
+        "<G> gravity  <D> daily  <Left> rewind",
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        draw_scaled_text(
+            line,
+            pos_message_x,
+            pos_message_y + font_size * i as f32,
+            font_size,
+            &game_state.styles.colors.grey_mid,
+        );
+    }
+}
+
+/// Step counter and simulation keys, shown on levels with manual steps
+fn render_sim_controls(game_state: &GameState) {
+    let level = match game_state.current_level() {
+        None => return,
+        Some(level) => level,
+    };
+
+    if !level.rules.is_manual {
+        return;
+    }
+
+    // Highlighted while moves are waiting to be simulated
+    let color = if level.needs_step {
+        &game_state.styles.colors.yellow_2
+    } else {
+        &game_state.styles.colors.grey_mid
+    };
+
+    let font_size = 12.0;
     draw_scaled_text(
-        "<R> to retry level",
-        pos_message_x,
-        pos_message_y,
+        format!(
+            "Step {}  <Space> simulate  <F> fast-forward",
+            level.step_count
+        )
+        .as_str(),
+        8.0,
+        SCREEN_H - font_size * 4.666,
         font_size,
-        &game_state.styles.colors.grey_mid,
+        color,
     );
 }
 
@@ -130,6 +266,7 @@ fn setup_level(game_state: &mut GameState) -> bool {
     game_state.planet_current_index = 0;
     game_state.sim_step = 0;
     game_state.sim_step_computed = 0;
+    game_state.sim_step_time = 0.0;
 
     play_sound_once(&game_state.sfx_level_start_01);
 
@@ -152,6 +289,10 @@ fn update_next_level(game_state: &mut GameState) {
     }
     // Change level
     else if is_key_pressed(KeyCode::F1) {
+        // No levels were loaded, there is nothing to switch to
+        if level_count == 0 {
+            return;
+        }
         match game_state.current_level_mut() {
             None => {}
             Some(level) => level.reset(),
@@ -164,6 +305,9 @@ fn update_next_level(game_state: &mut GameState) {
             Some(level) => level.reset(),
         }
     } else if is_key_pressed(KeyCode::F2) {
+        if level_count == 0 {
+            return;
+        }
         match game_state.current_level_mut() {
             None => {}
             Some(level) => level.reset(),
@@ -176,6 +320,9 @@ fn update_next_level(game_state: &mut GameState) {
             Some(level) => level.reset(),
         }
     } else if is_key_pressed(KeyCode::F3) {
+        if level_count == 0 {
+            return;
+        }
         // TODO(Jesus): Remove before release.
         match game_state.current_level_mut() {
             None => {}
@@ -187,6 +334,9 @@ fn update_next_level(game_state: &mut GameState) {
             Some(level) => level.reset(),
         }
     } else if is_key_pressed(KeyCode::F4) {
+        if level_count == 0 {
+            return;
+        }
         // TODO(Jesus): Remove before release.
         match game_state.current_level_mut() {
             None => {}
@@ -198,6 +348,29 @@ fn update_next_level(game_state: &mut GameState) {
             Some(level) => level.reset(),
         }
     }
+    // Dump the board in ASCII notation
+    else if is_key_pressed(KeyCode::F5) {
+        if let Some(level) = game_state.current_level() {
+            info!("{}\n{}", level.name, level.board());
+        }
+    }
+    // Switch between the levels and the daily puzzle
+    else if is_key_pressed(KeyCode::D) {
+        match game_state.current_level_mut() {
+            None => {}
+            Some(level) => level.reset(),
+        }
+        game_state.is_daily_active = !game_state.is_daily_active;
+        match game_state.current_level_mut() {
+            None => {}
+            Some(level) => level.reset(),
+        }
+    }
+
+    // The daily puzzle has no next level
+    if game_state.is_daily_active {
+        return;
+    }
 
     let level = match game_state.current_level_mut() {
         None => return,
@@ -220,94 +393,94 @@ fn update_next_level(game_state: &mut GameState) {
     }
 }
 
+fn update_daily(game_state: &mut GameState) {
+    // Wait for the player to leave the current puzzle before switching to the next day's
+    if game_state.is_daily_active {
+        return;
+    }
+
+    let day = daily::day_from_timestamp(miniquad::date::now());
+    if day == game_state.daily_day {
+        return;
+    }
+
+    game_state.daily_day = day;
+    game_state.daily_level = GameState::create_daily_level(day, &game_state.styles);
+    game_state.daily_best_score = None;
+    game_state.is_daily_solved = false;
+}
+
 fn update_sim(game_state: &mut GameState) {
-    // Simulation advances 1 step when a planet is placed or removed
+    // Simulation advances when a planet is placed or removed, or when the player simulates on
+    // manual levels, one step at a time until the run is over
     if game_state.sim_step_computed >= game_state.sim_step {
         return;
     }
 
+    let time = get_time();
+    let sim_step_time = game_state.sim_step_time;
+
     let level = match game_state.current_level_mut() {
         None => return,
         Some(level) => level,
     };
 
-    // Moves computation
-    let planets_clone = level.planets.clone();
-    let mut i: usize = 0;
-    for planet in &mut level.planets {
-        if let PlanetState::Placed(tile) = planet.state {
-            planet.sim_tile_delta.x = 0;
-            planet.sim_tile_delta.y = 0;
-
-            let mut j: usize = 0;
-            for other_planet in &planets_clone {
-                if let PlanetState::Placed(other_tile) = other_planet.state {
-                    if i == j {
-                        j += 1;
-                        continue;
-                    }
-                    // Row gravity
-                    else if other_tile.y == tile.y {
-                        if other_tile.x < tile.x && other_planet.has_gravity_right() {
-                            planet.sim_tile_delta.x += -1;
-                            planet.sim_tile_delta.x = clamp(planet.sim_tile_delta.x, -1, 1);
-                        } else if other_tile.x > tile.x && other_planet.has_gravity_left() {
-                            planet.sim_tile_delta.x += 1;
-                            planet.sim_tile_delta.x = clamp(planet.sim_tile_delta.x, -1, 1);
-                        }
-                    }
-                    // Column gravity
-                    else if other_tile.x == tile.x {
-                        if other_tile.y < tile.y && other_planet.has_gravity_down() {
-                            planet.sim_tile_delta.y += -1;
-                            planet.sim_tile_delta.y = clamp(planet.sim_tile_delta.y, -1, 1);
-                        } else if other_tile.y > tile.y && other_planet.has_gravity_up() {
-                            planet.sim_tile_delta.y += 1;
-                            planet.sim_tile_delta.y = clamp(planet.sim_tile_delta.y, -1, 1);
-                        }
-                    }
+    // The first step follows the move right away, later ones wait for planets to arrive
+    if level.sim_run.steps() > 0 && time - sim_step_time < SIM_STEP_INTERVAL {
+        return;
+    }
 
-                    j += 1;
-                }
-            }
+    let (board, events, halt) = level.sim_run.step(&level.board());
+    level.apply_board(&board);
+    level.step_count += 1;
 
-            if let PlanetState::Placed(_) = planet.state {
-                planet.sim_tile_delta.x = clamp(planet.sim_tile_delta.x, -1, 1);
-                planet.sim_tile_delta.y = clamp(planet.sim_tile_delta.y, -1, 1);
-                planet.state = PlanetState::Placed(tile + planet.sim_tile_delta);
-            }
-            i += 1;
+    // Planets that fell back to the stack are placed again from the mouse
+    for planet in &mut level.planets {
+        if planet.body.state == PlanetState::Pending {
+            planet.remove();
         }
     }
 
-    // Collisions computation
-    let planets_clone = level.planets.clone();
-    let mut i: usize = 0;
-    for planet in &mut level.planets {
-        if let PlanetState::Placed(tile) = planet.state {
-            let mut j: usize = 0;
-            for other_planet in &planets_clone {
-                if let PlanetState::Placed(other_tile) = other_planet.state {
-                    if i == j {
-                        j += 1;
-                        continue;
-                    }
-
-                    if tile == other_tile {
-                        planet.state = PlanetState::Colliding(tile);
-                        level.is_failed = true;
-                        continue;
-                    }
+    // Collisions and planets lost off the grid
+    if board.is_failed() {
+        level.is_failed = true;
+    }
+    if halt == Some(Halt::Oscillating) {
+        level.is_oscillating = true;
+    }
+    if halt == Some(Halt::LeftGrid) {
+        level.is_lost = true;
+        level.is_failed = true;
+    }
 
-                    j += 1;
-                }
-            }
+    let snapshot = level.snapshot();
+    level.timeline.push(snapshot);
 
-            i += 1;
-        }
+    let is_pushed = events
+        .iter()
+        .any(|event| matches!(event, Event::Pushed { .. }));
+    if is_pushed {
+        play_sound_once(&game_state.sfx_push_01);
+    }
+    let is_absorbed = events
+        .iter()
+        .any(|event| matches!(event, Event::Absorbed { .. }));
+    if is_absorbed {
+        play_sound_once(&game_state.sfx_absorb_01);
+    }
+    let is_merged = events
+        .iter()
+        .any(|event| matches!(event, Event::Merged { .. }));
+    if is_merged {
+        play_sound_once(&game_state.sfx_merge_01);
     }
 
+    game_state.planet_current_index = board.next_pending().unwrap_or(board.bodies.len());
+    game_state.sim_step_time = time;
     game_state.sim_step_computed += 1;
+    if halt.is_none() {
+        game_state.sim_step += 1;
+    }
 }
 
 fn update_win_condition(game_state: &mut GameState) {
@@ -319,22 +492,17 @@ fn update_win_condition(game_state: &mut GameState) {
         Some(i) => i >= level_count - 1,
     };
 
+    let is_daily_active = game_state.is_daily_active;
+
     let level = match game_state.current_level_mut() {
         None => return,
         Some(level) => level,
     };
 
-    // Check for stable system
-    for planet in &level.planets {
-        if let PlanetState::Placed(_) = planet.state {
-            level.is_stable = planet.sim_tile_delta.x == 0 && planet.sim_tile_delta.y == 0;
-        } else {
-            level.is_stable = false;
-        }
-
-        if !level.is_stable {
-            break;
-        }
+    // Check for stable system, manual levels also wait for the last moves to be simulated.
+    // Boards viewed on the timeline are in the past.
+    if level.timeline_view.is_none() {
+        level.is_stable = level.board().is_stable();
     }
 
     if level.is_stable {
@@ -346,14 +514,14 @@ fn update_win_condition(game_state: &mut GameState) {
             pos_message_x - 2.0,
             pos_message_y - 2.0,
             message_size + 4.0,
-            32.0 + 4.0,
+            48.0 + 4.0,
             colors.yellow_4,
         );
         draw_rectangle(
             pos_message_x,
             pos_message_y,
             message_size,
-            32.0,
+            48.0,
             colors.yellow_2,
         );
         draw_scaled_text(
@@ -365,7 +533,18 @@ fn update_win_condition(game_state: &mut GameState) {
         );
 
         pos_message_y += font_size;
-        let message = if is_last_level {
+        draw_scaled_text(
+            format!("Hints used: {}", level.hint_count).as_str(),
+            pos_message_x,
+            pos_message_y + font_size / 1.333,
+            font_size,
+            &colors.black_1,
+        );
+
+        pos_message_y += font_size;
+        let message = if is_daily_active {
+            "<D> back to levels"
+        } else if is_last_level {
             "Thanks for playing!"
         } else {
             "Click to continue"
@@ -391,6 +570,12 @@ fn update_win_condition(game_state: &mut GameState) {
         play_sound_failed = true;
     }
 
+    let mut play_sound_oscillating = false;
+    if level.is_oscillating && !level.was_oscillating {
+        level.was_oscillating = true;
+        play_sound_oscillating = true;
+    }
+
     if play_sound_stable {
         stop_sound(&game_state.music_level_end_01);
         play_sound(
@@ -405,6 +590,10 @@ fn update_win_condition(game_state: &mut GameState) {
     if play_sound_failed {
         play_sound_once(&game_state.sfx_explosion_01);
     }
+
+    if play_sound_oscillating {
+        play_sound_once(&game_state.sfx_planet_place_deny_01);
+    }
 }
 
 fn update_score(game_state: &mut GameState) {
@@ -417,6 +606,16 @@ fn update_score(game_state: &mut GameState) {
     }
 
     game_state.score = score;
+
+    // The daily puzzle keeps its own best score
+    if game_state.daily_level.is_stable {
+        let daily_score = 100 + game_state.daily_level.score;
+        game_state.daily_best_score = Some(match game_state.daily_best_score {
+            Some(best) => best.max(daily_score),
+            None => daily_score,
+        });
+        game_state.is_daily_solved = true;
+    }
 }
 
 fn update_planets(game_state: &mut GameState) {
@@ -433,21 +632,30 @@ fn update_planets(game_state: &mut GameState) {
 
     let tile = game_state.tile_highlighted;
     let planet_current_index = game_state.planet_current_index;
+    let is_sim_running = game_state.sim_step_computed < game_state.sim_step;
 
     let level = match game_state.current_level_mut() {
         None => return,
         Some(level) => level,
     };
 
-    if level.is_failed || level.is_stable {
+    if level.is_failed
+        || level.is_stable
+        || level.is_oscillating
+        || level.timeline_view.is_some()
+        || is_sim_running
+    {
         return;
     }
 
+    // Simulation has caught up with the last action, so this is the board the player sees
+    let snapshot_before = level.snapshot();
+
     let has_placed_all = planet_current_index >= level.planets.len();
 
     // Skip indices from placed planets
     if !has_placed_all {
-        if let PlanetState::Placed(_) = &level.planets[planet_current_index].state {
+        if let PlanetState::Placed(_) = &level.planets[planet_current_index].body.state {
             game_state.planet_current_index += 1;
             return;
         }
@@ -459,7 +667,7 @@ fn update_planets(game_state: &mut GameState) {
         let grid_offset = level.grid_offset();
 
         for planet in &level.planets {
-            match planet.state {
+            match planet.body.state {
                 PlanetState::Placed(other_tile) => {
                     if other_tile == tile {
                         is_tile_free = false;
@@ -476,7 +684,7 @@ fn update_planets(game_state: &mut GameState) {
 
             let mut next_index = 0;
             for planet in &level.planets {
-                if let PlanetState::Pending = planet.state {
+                if let PlanetState::Pending = planet.body.state {
                     break;
                 }
                 next_index += 1;
@@ -487,7 +695,6 @@ fn update_planets(game_state: &mut GameState) {
             // Planed was placed, advance simulation
             play_sound_place = true;
             score_delta = -1;
-            game_state.sim_step += 1;
         } else if is_mouse_in_grid {
             play_sound_place_deny = true;
         }
@@ -496,17 +703,16 @@ fn update_planets(game_state: &mut GameState) {
     else if input_click && has_placed_all && is_mouse_in_grid {
         let mut planet_index = 0;
         for planet in &mut level.planets {
-            match planet.state {
+            match planet.body.state {
                 PlanetState::Placed(other_tile) => {
                     if other_tile == tile {
-                        if planet.is_removable {
+                        if planet.body.is_removable {
                             planet.remove();
                             game_state.planet_current_index = planet_index;
 
                             // Planed was removed, advance simulation
                             play_sound_remove = true;
                             score_delta = -1;
-                            game_state.sim_step += 1;
 
                             break;
                         } else if is_mouse_in_grid {
@@ -538,6 +744,294 @@ fn update_planets(game_state: &mut GameState) {
     };
 
     level.score += score_delta;
+
+    let mut is_sim_started = false;
+    if play_sound_place || play_sound_remove {
+        level.history.push(snapshot_before);
+        level.future.clear();
+
+        // Manual levels wait for the player to simulate
+        if level.rules.is_manual {
+            level.needs_step = true;
+        } else {
+            level.sim_run = Run::new(level.rules.steps);
+            is_sim_started = true;
+        }
+
+        // Hints only apply to the board they were computed for
+        level.hint = None;
+        level.is_hint_unavailable = false;
+        level.hint_search = None;
+    }
+
+    if is_sim_started {
+        game_state.sim_step += 1;
+    }
+}
+
+fn update_simulate(game_state: &mut GameState) {
+    let is_step = is_key_pressed(KeyCode::Space);
+    let is_fast_forward = is_key_pressed(KeyCode::F);
+    if !is_step && !is_fast_forward {
+        return;
+    }
+
+    if game_state.sim_step_computed < game_state.sim_step {
+        return;
+    }
+
+    let level = match game_state.current_level_mut() {
+        None => return,
+        Some(level) => level,
+    };
+
+    if !level.rules.is_manual
+        || level.is_failed
+        || level.is_stable
+        || level.is_oscillating
+        || level.timeline_view.is_some()
+    {
+        return;
+    }
+
+    // Each simulate is a move of its own in the history
+    let snapshot = level.snapshot();
+    level.history.push(snapshot);
+    level.future.clear();
+
+    let steps = if is_fast_forward {
+        Steps::Settle
+    } else {
+        Steps::Count(1)
+    };
+    level.sim_run = Run::new(steps);
+
+    level.hint = None;
+    level.is_hint_unavailable = false;
+    level.hint_search = None;
+
+    game_state.sim_step += 1;
+}
+
+fn update_history(game_state: &mut GameState) {
+    let is_undo = is_key_pressed(KeyCode::Z);
+    let is_redo = is_key_pressed(KeyCode::Y);
+    if !is_undo && !is_redo {
+        return;
+    }
+
+    // Wait for the steps of the last move, history only keeps whole moves
+    if game_state.sim_step_computed < game_state.sim_step {
+        return;
+    }
+
+    let level = match game_state.current_level_mut() {
+        None => return,
+        Some(level) => level,
+    };
+
+    // A stable level has already been scored
+    if level.is_stable || level.timeline_view.is_some() {
+        return;
+    }
+
+    let has_changed = if is_undo { level.undo() } else { level.redo() };
+    let next_index = level.board().next_pending().unwrap_or(level.planets.len());
+
+    if has_changed {
+        game_state.planet_current_index = next_index;
+        play_sound_once(&game_state.sfx_planet_remove_01);
+    } else {
+        play_sound_once(&game_state.sfx_planet_remove_deny_01);
+    }
+}
+
+fn update_timeline(game_state: &mut GameState) {
+    let is_back = is_key_pressed(KeyCode::Left);
+    let is_forward = is_key_pressed(KeyCode::Right);
+    let is_resume = is_key_pressed(KeyCode::Enter);
+    let is_leave = is_key_pressed(KeyCode::Escape);
+
+    // The bar is thin, clicks around it count too
+    let rect = timeline_rect();
+    let is_click = is_mouse_button_pressed(MouseButton::Left)
+        && Rect::new(rect.x, rect.y - 4.0, rect.w, rect.h + 8.0).contains(game_state.mouse_pos);
+    let mouse_x = game_state.mouse_pos.x;
+
+    if !is_back && !is_forward && !is_resume && !is_leave && !is_click {
+        return;
+    }
+
+    // Wait for the steps of the last move, the timeline only changes between them
+    if game_state.sim_step_computed < game_state.sim_step {
+        return;
+    }
+
+    let level = match game_state.current_level_mut() {
+        None => return,
+        Some(level) => level,
+    };
+
+    // A stable level has already been scored
+    if level.is_stable {
+        return;
+    }
+
+    let last = level.timeline.len() - 1;
+    let mut is_resumed = false;
+    match level.timeline_view {
+        None if is_back => {
+            // Moves not simulated yet, or undone ones, are not on the timeline
+            let is_live_last = level.board() == level.timeline[last].board;
+            let index = if is_live_last {
+                last.saturating_sub(1)
+            } else {
+                last
+            };
+            level.view_timeline(index);
+        }
+        None if is_click => level.view_timeline(timeline_index(mouse_x, last + 1)),
+        None => return,
+        Some(_) if is_resume => is_resumed = level.resume_timeline(),
+        Some(_) if is_leave => level.stop_viewing_timeline(),
+        Some(_) if is_click => level.view_timeline(timeline_index(mouse_x, last + 1)),
+        Some(index) if is_back => level.view_timeline(index.saturating_sub(1)),
+        Some(index) if is_forward && index < last => level.view_timeline(index + 1),
+        Some(_) if is_forward => level.stop_viewing_timeline(),
+        Some(_) => return,
+    }
+
+    let next_index = level.board().next_pending().unwrap_or(level.planets.len());
+    game_state.planet_current_index = next_index;
+
+    if is_resumed {
+        play_sound_once(&game_state.sfx_planet_remove_01);
+    }
+}
+
+/// Bar at the bottom of the screen showing the steps of the current attempt
+fn timeline_rect() -> Rect {
+    Rect::new(8.0, SCREEN_H - 5.0, SCREEN_W - 16.0, 4.0)
+}
+
+/// Position of the step at `index` on the timeline bar
+fn timeline_x(index: usize, count: usize) -> f32 {
+    let rect = timeline_rect();
+    if count < 2 {
+        return rect.x + rect.w;
+    }
+
+    rect.x + rect.w * index as f32 / (count - 1) as f32
+}
+
+/// Step of the timeline bar closest to `x`
+fn timeline_index(x: f32, count: usize) -> usize {
+    let rect = timeline_rect();
+    if count < 2 {
+        return 0;
+    }
+
+    let t = ((x - rect.x) / rect.w).clamp(0.0, 1.0);
+    (t * (count - 1) as f32).round() as usize
+}
+
+fn render_timeline(game_state: &GameState) {
+    let level = match game_state.current_level() {
+        None => return,
+        Some(level) => level,
+    };
+
+    let count = level.timeline.len();
+    if count < 2 {
+        return;
+    }
+
+    let colors = &game_state.styles.colors;
+    let rect = timeline_rect();
+    draw_rectangle(rect.x, rect.y, rect.w, rect.h, colors.grey_dark);
+
+    // Collisions and oscillations stand out, so they are easy to rewind from
+    for (i, snapshot) in level.timeline.iter().enumerate() {
+        let color = if snapshot.board.is_failed() || snapshot.is_lost {
+            colors.red_light
+        } else if snapshot.is_oscillating {
+            colors.yellow_4
+        } else {
+            colors.grey_mid
+        };
+        let x = timeline_x(i, count);
+        draw_line(x, rect.y, x, rect.y + rect.h, 1.0, color);
+    }
+
+    let (index, color) = match level.timeline_view {
+        None => (count - 1, colors.white),
+        Some(index) => (index, colors.yellow_2),
+    };
+    let x = timeline_x(index, count);
+    draw_rectangle(x - 1.5, rect.y - 2.0, 3.0, rect.h + 4.0, color);
+
+    if let Some(index) = level.timeline_view {
+        let font_size = 12.0;
+        draw_scaled_text(
+            format!(
+                "Step {}  <Left> <Right> scrub  <Enter> resume (-1)  <Esc> back",
+                level.timeline[index].step_count
+            )
+            .as_str(),
+            8.0,
+            SCREEN_H - font_size * 5.666,
+            font_size,
+            &colors.yellow_2,
+        );
+    }
+}
+
+fn update_gravity_overlay(game_state: &mut GameState) {
+    if is_key_pressed(KeyCode::G) {
+        game_state.is_gravity_overlay_visible = !game_state.is_gravity_overlay_visible;
+    }
+}
+
+fn update_hint(game_state: &mut GameState) {
+    // The board is still changing, the hint would be for a stale one
+    if game_state.sim_step_computed < game_state.sim_step {
+        return;
+    }
+
+    let level = match game_state.current_level_mut() {
+        None => return,
+        Some(level) => level,
+    };
+
+    let can_start = !level.is_failed
+        && !level.is_stable
+        && !level.is_oscillating
+        && level.timeline_view.is_none()
+        && level.hint.is_none()
+        && level.hint_search.is_none();
+    if is_key_pressed(KeyCode::H) && can_start {
+        // Searched from the current board, so hints also work after a bad move
+        let solver = Solver::new(&level.board(), Limits::default());
+        level.hint_search = Some(Rc::new(RefCell::new(solver)));
+    }
+
+    let search = match &level.hint_search {
+        None => return,
+        Some(solver) => match solver.borrow_mut().run(HINT_BOARDS_PER_FRAME) {
+            None => return,
+            Some(search) => search,
+        },
+    };
+    level.hint_search = None;
+
+    match search.outcome {
+        Outcome::Solved(actions) if !actions.is_empty() => {
+            level.hint = Some(actions[0]);
+            level.hint_count += 1;
+            level.score -= HINT_SCORE_COST;
+        }
+        _ => level.is_hint_unavailable = true,
+    }
 }
 
 fn render_planets(game_state: &mut GameState) {
@@ -551,9 +1045,10 @@ fn render_planets(game_state: &mut GameState) {
 
             for planet in &mut level.planets {
                 planet.render_stack(planet_i, &game_state_clone);
-                match planet.state {
+                match planet.body.state {
                     PlanetState::Placed(_) => planet.render(&game_state_clone),
                     PlanetState::Colliding(_) => planet.render(&game_state_clone),
+                    PlanetState::Absorbed => {}
                     PlanetState::Pending => {
                         if planet_i == game_state_clone.planet_current_index {
                             planet.render(&game_state_clone)
@@ -575,7 +1070,12 @@ fn render_planets(game_state: &mut GameState) {
 
     let has_placed_all = planet_current_index >= level.planets.len();
 
-    if has_placed_all && !level.is_stable && !level.is_failed {
+    if has_placed_all
+        && !level.is_stable
+        && !level.is_failed
+        && !level.is_oscillating
+        && level.timeline_view.is_none()
+    {
         draw_scaled_text(
             "Remove a planet",
             8.0,
@@ -586,15 +1086,165 @@ fn render_planets(game_state: &mut GameState) {
     }
 }
 
+/// Ghosts of where planets would end up if the pending planet was placed on the highlighted tile
+fn render_preview(game_state: &GameState) {
+    let level = match game_state.current_level() {
+        None => return,
+        Some(level) => level,
+    };
+
+    let is_sim_running = game_state.sim_step_computed < game_state.sim_step;
+    if level.is_failed
+        || level.is_stable
+        || level.is_oscillating
+        || level.timeline_view.is_some()
+        || is_sim_running
+        || !game_state.is_mouse_in_grid
+    {
+        return;
+    }
+
+    let planet_current_index = game_state.planet_current_index;
+    let tile = game_state.tile_highlighted;
+
+    let board = level.board();
+    let is_pending = board
+        .bodies
+        .get(planet_current_index)
+        .is_some_and(|body| body.state == PlanetState::Pending);
+    if !is_pending || board.planet_at(tile).is_some() {
+        return;
+    }
+
+    let (next, _, _) = solver::apply(
+        &board,
+        Action::Place {
+            planet: planet_current_index,
+            tile,
+        },
+    );
+
+    for (i, (planet, body)) in level.planets.iter().zip(&next.bodies).enumerate() {
+        let tile_from = if i == planet_current_index {
+            tile
+        } else {
+            match board.bodies[i].tile() {
+                None => continue,
+                Some(tile_from) => tile_from,
+            }
+        };
+
+        let (tile_to, is_colliding) = match body.state {
+            PlanetState::Pending | PlanetState::Absorbed => continue,
+            PlanetState::Placed(tile_to) => (tile_to, false),
+            PlanetState::Colliding(tile_to) => (tile_to, true),
+        };
+
+        let pos_to = level.tile_center(tile_to);
+        if is_colliding {
+            let mut color = game_state.styles.colors.red_light;
+            color.a = 0.3;
+            draw_rectangle(
+                pos_to.x - TILE_SIZE_X / 2.0,
+                pos_to.y - TILE_SIZE_Y / 2.0,
+                TILE_SIZE_X,
+                TILE_SIZE_Y,
+                color,
+            );
+        }
+
+        if tile_to == tile_from {
+            continue;
+        }
+
+        let pos_from = level.tile_center(tile_from);
+        let mut color = planet.color;
+        color.a = 0.3;
+        draw_line(pos_from.x, pos_from.y, pos_to.x, pos_to.y, 2.0, color);
+        draw_circle(pos_to.x, pos_to.y, planet.radius(), color);
+    }
+}
+
+fn render_hint(game_state: &GameState) {
+    let level = match game_state.current_level() {
+        None => return,
+        Some(level) => level,
+    };
+
+    if level.hint_search.is_some() {
+        let font_size = 12.0;
+        draw_scaled_text(
+            "Looking for a hint...",
+            8.0,
+            SCREEN_H - font_size * 3.666,
+            font_size,
+            &game_state.styles.colors.yellow_3,
+        );
+    }
+
+    if level.is_hint_unavailable {
+        let font_size = 12.0;
+        draw_scaled_text(
+            "No solution from here, <Z> to undo or <R> to retry",
+            8.0,
+            SCREEN_H - font_size * 3.666,
+            font_size,
+            &game_state.styles.colors.red_light,
+        );
+    }
+
+    let tile = match level.hint {
+        None => return,
+        Some(Action::Step) => {
+            let font_size = 12.0;
+            draw_scaled_text(
+                "Hint: <Space> to simulate",
+                8.0,
+                SCREEN_H - font_size * 3.666,
+                font_size,
+                &game_state.styles.colors.yellow_3,
+            );
+            return;
+        }
+        Some(Action::Place { tile, .. }) => tile,
+        Some(Action::Remove { planet }) => match level.planets[planet].body.tile() {
+            None => return,
+            Some(tile) => tile,
+        },
+    };
+
+    let grid_offset = level.grid_offset();
+    let x = tile.x as f32 * TILE_SIZE_X + grid_offset.x;
+    let y = tile.y as f32 * TILE_SIZE_Y + grid_offset.y;
+
+    let mut color = game_state.styles.colors.yellow_3;
+    color.a = 0.3;
+    draw_rectangle(x, y, TILE_SIZE_X, TILE_SIZE_Y, color);
+    draw_rectangle_lines(
+        x,
+        y,
+        TILE_SIZE_X,
+        TILE_SIZE_Y,
+        2.0,
+        game_state.styles.colors.yellow_3,
+    );
+}
+
 fn render_level_failed(game_state: &GameState) {
     let level = match game_state.current_level() {
         None => return,
         Some(level) => level,
     };
 
-    if level.is_failed {
+    if level.is_failed && level.timeline_view.is_none() {
+        let message = if level.is_lost {
+            "Planet lost! <Z> undo <R> retry"
+        } else {
+            "Collision! <Z> undo <R> retry"
+        };
+
         let font_size = 16.0;
-        let message_size = 162.0;
+        let message_size = 206.0;
         let pos_message_x = SCREEN_W / 2.0 - message_size / 2.0;
         let pos_message_y = (SCREEN_H * 0.333) - font_size;
         draw_rectangle(
@@ -612,7 +1262,42 @@ fn render_level_failed(game_state: &GameState) {
             game_state.styles.colors.red_dark,
         );
         draw_scaled_text(
-            "Collision! <R> to retry",
+            message,
+            pos_message_x,
+            pos_message_y + font_size / 1.333,
+            font_size,
+            &game_state.styles.colors.white,
+        );
+    }
+}
+
+fn render_level_oscillating(game_state: &GameState) {
+    let level = match game_state.current_level() {
+        None => return,
+        Some(level) => level,
+    };
+
+    if level.is_oscillating && level.timeline_view.is_none() {
+        let font_size = 16.0;
+        let message_size = 226.0;
+        let pos_message_x = SCREEN_W / 2.0 - message_size / 2.0;
+        let pos_message_y = (SCREEN_H * 0.333) - font_size;
+        draw_rectangle(
+            pos_message_x - 2.0,
+            pos_message_y - 2.0,
+            message_size + 4.0,
+            16.0 + 4.0,
+            game_state.styles.colors.blue_2,
+        );
+        draw_rectangle(
+            pos_message_x,
+            pos_message_y,
+            message_size,
+            16.0,
+            game_state.styles.colors.blue_4,
+        );
+        draw_scaled_text(
+            "Oscillation! <Z> undo <R> retry",
             pos_message_x,
             pos_message_y + font_size / 1.333,
             font_size,
@@ -630,10 +1315,11 @@ fn render_grid(game_state: &mut GameState) {
 
     let grid_size_px: f32::Vec2;
     let grid_offset: f32::Vec2;
-    let grid_tiles: IVec2;
+    let grid_tiles: Tile;
 
     let is_stable: bool;
     let is_failed: bool;
+    let board: Board;
 
     match game_state.current_level() {
         Some(level) => {
@@ -642,13 +1328,15 @@ fn render_grid(game_state: &mut GameState) {
             grid_tiles = level.grid_tiles;
             is_stable = level.is_stable;
             is_failed = level.is_failed;
+            board = level.board();
         }
         None => {
             grid_size_px = f32::Vec2::ZERO;
             grid_offset = f32::Vec2::ZERO;
-            grid_tiles = IVec2::ZERO;
+            grid_tiles = Tile::ZERO;
             is_stable = false;
             is_failed = false;
+            board = Board::new(Tile::ZERO, Vec::new());
         }
     }
 
@@ -699,6 +1387,22 @@ fn render_grid(game_state: &mut GameState) {
         }
     }
 
+    // Shade the tiles pulled by each field, overlapping fields add up
+    if game_state.is_gravity_overlay_visible {
+        for planet in 0..board.bodies.len() {
+            for direction in Direction::ALL {
+                let mut color = styles.colors.by_direction(direction);
+                color.a = 0.2;
+
+                for tile in board.gravity_reach(planet, direction) {
+                    let x = tile.x as f32 * cell_w + grid_offset.x;
+                    let y = tile.y as f32 * cell_h + grid_offset.y;
+                    draw_rectangle(x, y, cell_w, cell_h, color);
+                }
+            }
+        }
+    }
+
     // Draw vertical grid lines
     for i in 0..=grid_tiles.x {
         let x = i as f32 * cell_w;
@@ -724,6 +1428,44 @@ fn render_grid(game_state: &mut GameState) {
             color_lines,
         );
     }
+
+    // Draw the border the way it treats planets pulled past it
+    let (color_border, dash) = match board.rules.edge {
+        Edge::Open => return,
+        Edge::Wall => (styles.colors.grey_light, None),
+        Edge::Wrap => (styles.colors.blue_3, Some(4.0)),
+        Edge::Fall => (styles.colors.red_light, Some(4.0)),
+        Edge::Return => (styles.colors.yellow_3, Some(4.0)),
+    };
+    let corner_min = grid_offset;
+    let corner_max = grid_offset + grid_size_px;
+    let corners = [
+        corner_min,
+        f32::vec2(corner_max.x, corner_min.y),
+        corner_max,
+        f32::vec2(corner_min.x, corner_max.y),
+    ];
+    for i in 0..corners.len() {
+        let from = corners[i];
+        let to = corners[(i + 1) % corners.len()];
+        match dash {
+            None => draw_line(from.x, from.y, to.x, to.y, 3.0, color_border),
+            Some(dash) => draw_dashed_line(from, to, dash, 2.0, color_border),
+        }
+    }
+}
+
+fn draw_dashed_line(from: f32::Vec2, to: f32::Vec2, dash: f32, thickness: f32, color: Color) {
+    let length = from.distance(to);
+    let direction = (to - from) / length;
+
+    let mut distance = 0.0;
+    while distance < length {
+        let start = from + direction * distance;
+        let end = from + direction * (distance + dash).min(length);
+        draw_line(start.x, start.y, end.x, end.y, thickness, color);
+        distance += dash * 2.0;
+    }
 }
 
 fn configure() {
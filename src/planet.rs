@@ -1,7 +1,11 @@
+use akj_21::{
+    rules::Edge,
+    sim::{Body, Direction, Kind, PlanetState, Tile, MASS_DEFAULT, RANGE_UNLIMITED},
+};
 use macroquad::{
     color::{self, Color},
-    math::{f32, IVec2},
-    shapes::{draw_circle, draw_line, draw_poly, draw_rectangle},
+    math::f32,
+    shapes::{draw_circle, draw_circle_lines, draw_line, draw_poly, draw_rectangle},
     texture::{draw_texture, draw_texture_ex, DrawTextureParams},
 };
 
@@ -9,48 +13,34 @@ use crate::{constants::*, game_state::GameState, text::draw_scaled_text};
 
 #[derive(Clone, Copy)]
 pub struct Planet {
-    pub state: PlanetState,
+    pub body: Body,
     pub size: f32,
     pub color: Color,
 
     pub render_pos: f32::Vec2,
-
-    /// Up, down, left, right
-    pub gravity_field: u8,
-
-    pub is_removable: bool,
-
-    pub sim_tile_delta: IVec2,
 }
 
 impl Planet {
-    pub fn new(
-        gravity_field: u8,
-        state: PlanetState,
-        is_removable: bool,
-        size: f32,
-        color: Color,
-    ) -> Self {
-        let sim_tile_next = IVec2::ZERO;
-
+    pub fn new(body: Body, size: f32, color: Color) -> Self {
         let render_pos = f32::Vec2::NEG_ONE;
 
         Self {
-            gravity_field,
-            state,
-            is_removable,
+            body,
 
             render_pos,
 
             size,
             color,
-
-            sim_tile_delta: sim_tile_next,
         }
     }
 
-    pub fn place(&mut self, tile: IVec2, grid_offset: f32::Vec2) {
-        self.state = PlanetState::Placed(tile);
+    /// Drawn radius, heavier planets look heavier
+    pub fn radius(&self) -> f32 {
+        self.size + MASS_SIZE_STEP * self.body.mass.saturating_sub(MASS_DEFAULT) as f32
+    }
+
+    pub fn place(&mut self, tile: Tile, grid_offset: f32::Vec2) {
+        self.body.state = PlanetState::Placed(tile);
 
         if self.render_pos.x < 0.0 || self.render_pos.y < 0.0 {
             self.render_pos.x = tile.x as f32 * TILE_SIZE_X + grid_offset.x + TILE_SIZE_X / 2.0;
@@ -59,26 +49,13 @@ impl Planet {
     }
 
     pub fn remove(&mut self) {
-        self.state = PlanetState::Pending;
+        self.body.state = PlanetState::Pending;
         self.render_pos.x = -1.0;
         self.render_pos.y = -1.0;
     }
 
-    pub fn has_gravity_up(&self) -> bool {
-        self.gravity_field & 0b1000 > 0
-    }
-    pub fn has_gravity_down(&self) -> bool {
-        self.gravity_field & 0b0100 > 0
-    }
-    pub fn has_gravity_left(&self) -> bool {
-        self.gravity_field & 0b0010 > 0
-    }
-    pub fn has_gravity_right(&self) -> bool {
-        self.gravity_field & 0b0001 > 0
-    }
-
     pub fn render(&mut self, game_state: &GameState) {
-        match self.state {
+        match self.body.state {
             PlanetState::Pending => {
                 draw_scaled_text(
                     "Place planet",
@@ -91,23 +68,26 @@ impl Planet {
                 let x = game_state.mouse_pos.x;
                 let y = game_state.mouse_pos.y;
 
-                draw_circle(48.0, 48.0, self.size * 2.0, self.color);
+                self.draw_body(48.0, 48.0, self.radius() * 2.0, game_state);
                 self.draw_gravity_arrows(48.0, 48.0, 2.0, game_state);
-                draw_circle(x, y, self.size, self.color);
+                self.draw_body(x, y, self.radius(), game_state);
                 self.draw_gravity_arrows(x, y, 1.0, game_state);
             }
             PlanetState::Placed(tile) => {
                 let grid_offset: f32::Vec2;
                 let is_failed: bool;
+                let is_wrapping: bool;
 
                 match game_state.current_level() {
                     Some(level) => {
                         grid_offset = level.grid_offset();
                         is_failed = level.is_failed;
+                        is_wrapping = level.rules.edge == Edge::Wrap;
                     }
                     None => {
                         grid_offset = f32::Vec2::ZERO;
                         is_failed = false;
+                        is_wrapping = false;
                     }
                 }
 
@@ -116,15 +96,17 @@ impl Planet {
                     tile.y as f32 * TILE_SIZE_Y + grid_offset.y + TILE_SIZE_Y / 2.0,
                 );
 
-                // Initialize render_pos if needed
-                if self.render_pos.x < 0.0 || self.render_pos.y < 0.0 {
+                // Initialize render_pos if needed, or jump across the grid after wrapping
+                let is_wrapped =
+                    is_wrapping && self.render_pos.distance(target) > TILE_SIZE_PX * 1.5;
+                if self.render_pos.x < 0.0 || self.render_pos.y < 0.0 || is_wrapped {
                     self.render_pos.x = target.x;
                     self.render_pos.y = target.y;
                 }
 
                 self.render_pos = self.render_pos.move_towards(target, 2.0);
 
-                if !self.is_removable && !is_failed {
+                if !self.body.is_removable && !is_failed {
                     let mut color = game_state.styles.colors.red_dark;
                     color.a = 0.8;
                     let cell_w = TILE_SIZE_X;
@@ -137,8 +119,14 @@ impl Planet {
                         color,
                     );
                 }
-                draw_circle(self.render_pos.x, self.render_pos.y, self.size, self.color);
+                self.draw_body(
+                    self.render_pos.x,
+                    self.render_pos.y,
+                    self.radius(),
+                    game_state,
+                );
                 self.draw_gravity_arrows(self.render_pos.x, self.render_pos.y, 1.0, game_state);
+                self.draw_anchor(self.render_pos.x, self.render_pos.y, 1.0, game_state);
             }
             PlanetState::Colliding(tile) => {
                 let grid_offset: f32::Vec2;
@@ -158,6 +146,7 @@ impl Planet {
                 let texture = &game_state.texture_explosion_01;
                 draw_texture(texture, x, y, color::WHITE);
             }
+            PlanetState::Absorbed => {}
         }
     }
 
@@ -166,62 +155,65 @@ impl Planet {
         let y = 32.0 + 36.0 * index as f32;
         let scale = 2.0;
 
-        match self.state {
+        match self.body.state {
             PlanetState::Pending => {
                 if index == game_state.planet_current_index {
                     let mut color = game_state.styles.colors.yellow_1;
                     color.a = 0.2;
                     draw_rectangle(
-                        x - self.size * scale * 1.2,
-                        y - self.size * scale * 1.2,
-                        self.size * 2.0 * scale * 1.2,
-                        self.size * 2.0 * scale * 1.2,
+                        x - self.radius() * scale * 1.2,
+                        y - self.radius() * scale * 1.2,
+                        self.radius() * 2.0 * scale * 1.2,
+                        self.radius() * 2.0 * scale * 1.2,
                         color,
                     );
                 }
-                draw_circle(x, y, self.size * scale, self.color);
+                self.draw_body(x, y, self.radius() * scale, game_state);
                 self.draw_gravity_arrows(x, y, scale, game_state);
+                self.draw_anchor(x, y, scale, game_state);
             }
-            PlanetState::Placed(_) => {
-                draw_circle(x, y, self.size * scale, self.color);
+            PlanetState::Placed(_) | PlanetState::Absorbed => {
+                self.draw_body(x, y, self.radius() * scale, game_state);
                 self.draw_gravity_arrows(x, y, scale, game_state);
+                self.draw_anchor(x, y, scale, game_state);
                 let mut color_line = game_state.styles.colors.red_light;
                 color_line.a = 0.8;
                 draw_line(
-                    x - self.size * scale,
-                    y - self.size * scale,
-                    x + self.size * scale,
-                    y + self.size * scale,
+                    x - self.radius() * scale,
+                    y - self.radius() * scale,
+                    x + self.radius() * scale,
+                    y + self.radius() * scale,
                     4.0,
                     color_line,
                 );
                 draw_line(
-                    x + self.size * scale,
-                    y - self.size * scale,
-                    x - self.size * scale,
-                    y + self.size * scale,
+                    x + self.radius() * scale,
+                    y - self.radius() * scale,
+                    x - self.radius() * scale,
+                    y + self.radius() * scale,
                     4.0,
                     color_line,
                 );
             }
             PlanetState::Colliding(_) => {
-                draw_circle(x, y, self.size * scale, self.color);
+                self.draw_body(x, y, self.radius() * scale, game_state);
                 self.draw_gravity_arrows(x, y, scale, game_state);
+                self.draw_anchor(x, y, scale, game_state);
                 let mut color_line = game_state.styles.colors.red_light;
                 color_line.a = 0.8;
                 draw_line(
-                    x - self.size * scale,
-                    y - self.size * scale,
-                    x + self.size * scale,
-                    y + self.size * scale,
+                    x - self.radius() * scale,
+                    y - self.radius() * scale,
+                    x + self.radius() * scale,
+                    y + self.radius() * scale,
                     4.0,
                     color_line,
                 );
                 draw_line(
-                    x + self.size * scale,
-                    y - self.size * scale,
-                    x - self.size * scale,
-                    y + self.size * scale,
+                    x + self.radius() * scale,
+                    y - self.radius() * scale,
+                    x - self.radius() * scale,
+                    y + self.radius() * scale,
                     4.0,
                     color_line,
                 );
@@ -243,29 +235,87 @@ impl Planet {
                 );
             }
         }
+
+        self.draw_gravity_ranges(x, y, scale, game_state);
     }
 
-    fn draw_gravity_arrows(&self, x: f32, y: f32, scale: f32, game_state: &GameState) {
-        let arrow_size = 4.0 * scale;
-        let arrow_color = game_state.styles.colors.red_dark;
-        if self.has_gravity_up() {
-            draw_poly(x, y - arrow_size, 3, arrow_size, 90.0, arrow_color);
-        }
-        if self.has_gravity_down() {
-            draw_poly(x, y + arrow_size, 3, arrow_size, -90.0, arrow_color);
+    /// Labels every field with a finite range with its reach in tiles, just outside the planet
+    fn draw_gravity_ranges(&self, x: f32, y: f32, scale: f32, game_state: &GameState) {
+        let font_size = 12.0;
+        let distance = self.radius() * scale + 4.0;
+
+        for direction in Direction::ALL {
+            let range = self.body.gravity_range(direction);
+            if !self.body.has_gravity(direction) || range == RANGE_UNLIMITED {
+                continue;
+            }
+
+            let offset = direction.offset();
+            draw_scaled_text(
+                &range.to_string(),
+                x + offset.x as f32 * distance - font_size / 4.0,
+                y + offset.y as f32 * distance + font_size / 4.0,
+                font_size,
+                &game_state.styles.colors.white,
+            );
         }
-        if self.has_gravity_left() {
-            draw_poly(x - arrow_size, y, 3, arrow_size, 0.0, arrow_color);
+    }
+
+    /// Black holes are dark with a ring of the planet's colour
+    fn draw_body(&self, x: f32, y: f32, radius: f32, game_state: &GameState) {
+        match self.body.kind {
+            Kind::Planet => draw_circle(x, y, radius, self.color),
+            Kind::BlackHole => {
+                draw_circle(x, y, radius, game_state.styles.colors.black_2);
+                draw_circle_lines(x, y, radius, 2.0, self.color);
+            }
         }
-        if self.has_gravity_right() {
-            draw_poly(x + arrow_size, y, 3, arrow_size, -180.0, arrow_color);
+    }
+
+    /// Padlock over anchored planets, which gravity can't move
+    fn draw_anchor(&self, x: f32, y: f32, scale: f32, game_state: &GameState) {
+        if !self.body.is_anchored {
+            return;
         }
+
+        let size = 8.0 * scale;
+        draw_texture_ex(
+            &game_state.texture_padlock_locked,
+            x - size / 2.0,
+            y - size / 2.0,
+            color::WHITE,
+            DrawTextureParams {
+                dest_size: Some(f32::Vec2::splat(size)),
+                ..Default::default()
+            },
+        );
     }
-}
 
-#[derive(PartialEq, Clone, Copy)]
-pub enum PlanetState {
-    Pending,
-    Placed(IVec2),
-    Colliding(IVec2),
+    fn draw_gravity_arrows(&self, x: f32, y: f32, scale: f32, game_state: &GameState) {
+        let arrow_size = 4.0 * scale;
+        for direction in Direction::ALL {
+            if !self.body.has_gravity(direction) {
+                continue;
+            }
+
+            // On the side of the field, pointing back at the planet when pulling or away from it
+            // when pushing
+            let offset = direction.offset();
+            let side = f32::Vec2::new(offset.x as f32, offset.y as f32).normalize();
+            let (rotation, arrow_color) = if self.body.is_pushing(direction) {
+                (side.y.atan2(side.x), game_state.styles.colors.blue_1)
+            } else {
+                ((-side.y).atan2(-side.x), game_state.styles.colors.red_dark)
+            };
+            let rotation = rotation.to_degrees();
+            draw_poly(
+                x + side.x * arrow_size,
+                y + side.y * arrow_size,
+                3,
+                arrow_size,
+                rotation,
+                arrow_color,
+            );
+        }
+    }
 }
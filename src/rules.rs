@@ -0,0 +1,157 @@
+//! Simulation rules a level can change. The defaults are the rules most levels use.
+
+/// How far along its row or column a gravity field can reach.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Reach {
+    /// Every planet in range is pulled
+    #[default]
+    All,
+    /// Only the nearest planet in each direction is pulled, shielding the ones behind it
+    Nearest,
+}
+
+impl Reach {
+    pub const ALL: [Reach; 2] = [Reach::All, Reach::Nearest];
+
+    /// Name used in level files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Reach::All => "all",
+            Reach::Nearest => "nearest",
+        }
+    }
+}
+
+/// Which axis wins when a planet is pulled along both its row and its column in the same step.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Priority {
+    /// Both pulls apply and the planet moves diagonally
+    #[default]
+    Both,
+    /// Any pull along the row cancels the pulls along the column
+    Row,
+    /// Any pull along the column cancels the pulls along the row
+    Column,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Both, Priority::Row, Priority::Column];
+
+    /// Name used in level files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Priority::Both => "both",
+            Priority::Row => "row",
+            Priority::Column => "column",
+        }
+    }
+}
+
+/// Which planets collide, and what happens when they do.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Collision {
+    /// Planets ending a step on the same tile, or meeting on the way
+    #[default]
+    Explode,
+    /// Only planets ending a step on the same tile, others swap places or pass through each other
+    Swap,
+    /// Planets ending a step on the same tile merge into a heavier one pulling wherever either of
+    /// them did, others pass through each other
+    Merge,
+    /// Like [`Collision::Merge`], but gravity both planets had in the same direction cancels out
+    Xor,
+}
+
+impl Collision {
+    pub const ALL: [Collision; 4] = [
+        Collision::Explode,
+        Collision::Swap,
+        Collision::Merge,
+        Collision::Xor,
+    ];
+
+    /// Name used in level files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Collision::Explode => "explode",
+            Collision::Swap => "swap",
+            Collision::Merge => "merge",
+            Collision::Xor => "xor",
+        }
+    }
+}
+
+/// What happens to planets pulled past the border of the grid.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edge {
+    /// They keep drifting outside the grid, where they still pull and get pulled
+    #[default]
+    Open,
+    /// They stop at the border
+    Wall,
+    /// They come back in on the opposite side
+    Wrap,
+    /// They are lost and the level is failed
+    Fall,
+    /// They go back to the stack, to be placed again. Planets that can't be removed are lost
+    /// instead, as with [`Edge::Fall`]
+    Return,
+}
+
+impl Edge {
+    pub const ALL: [Edge; 5] = [Edge::Open, Edge::Wall, Edge::Wrap, Edge::Fall, Edge::Return];
+
+    /// Name used in level files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Edge::Open => "open",
+            Edge::Wall => "wall",
+            Edge::Wrap => "wrap",
+            Edge::Fall => "fall",
+            Edge::Return => "return",
+        }
+    }
+}
+
+/// How many simulation steps run after every place or remove.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Steps {
+    /// Up to this many, stopping early once planets stop moving or collide
+    Count(u8),
+    /// Until planets stop moving, collide, leave the grid or repeat an earlier configuration
+    Settle,
+}
+
+impl Default for Steps {
+    fn default() -> Self {
+        Steps::Count(1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RuleSet {
+    pub reach: Reach,
+    /// Keeps a planet's movement within one tile per axis, clamping after every pull so the
+    /// order of the planets matters. Otherwise pulls add up.
+    pub is_clamped: bool,
+    pub priority: Priority,
+    pub collision: Collision,
+    pub edge: Edge,
+    pub steps: Steps,
+    /// Moves don't run the simulation, the player steps it when the system is ready
+    pub is_manual: bool,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            reach: Reach::default(),
+            is_clamped: true,
+            priority: Priority::default(),
+            collision: Collision::default(),
+            edge: Edge::default(),
+            steps: Steps::default(),
+            is_manual: false,
+        }
+    }
+}
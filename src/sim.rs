@@ -0,0 +1,1338 @@
+use std::ops::{Add, AddAssign, Sub};
+
+use crate::rules::{Collision, Edge, Priority, Reach, RuleSet, Steps};
+
+/// Grid coordinate, kept separate from macroquad's `IVec2` so the simulation can run without a
+/// window or audio device.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tile {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Tile {
+    pub const ZERO: Self = Self::new(0, 0);
+
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Add for Tile {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Tile {
+    fn add_assign(&mut self, other: Self) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
+impl Sub for Tile {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+/// Direction of a gravity field, pointing from the planet towards the planets it pulls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    /// Position of this direction in a planet's gravity ranges
+    pub fn index(&self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+            Direction::UpLeft => 4,
+            Direction::UpRight => 5,
+            Direction::DownLeft => 6,
+            Direction::DownRight => 7,
+        }
+    }
+
+    /// This direction's bit in a planet's gravity field. Diagonals use the high four bits, so
+    /// fields without them read the same as before diagonals existed.
+    pub fn bit(&self) -> u8 {
+        match self {
+            Direction::Up => 0b0000_1000,
+            Direction::Down => 0b0000_0100,
+            Direction::Left => 0b0000_0010,
+            Direction::Right => 0b0000_0001,
+            Direction::UpLeft => 0b1000_0000,
+            Direction::UpRight => 0b0100_0000,
+            Direction::DownLeft => 0b0010_0000,
+            Direction::DownRight => 0b0001_0000,
+        }
+    }
+
+    /// One tile in this direction
+    pub fn offset(&self) -> Tile {
+        match self {
+            Direction::Up => Tile::new(0, -1),
+            Direction::Down => Tile::new(0, 1),
+            Direction::Left => Tile::new(-1, 0),
+            Direction::Right => Tile::new(1, 0),
+            Direction::UpLeft => Tile::new(-1, -1),
+            Direction::UpRight => Tile::new(1, -1),
+            Direction::DownLeft => Tile::new(-1, 1),
+            Direction::DownRight => Tile::new(1, 1),
+        }
+    }
+
+    /// Direction and distance in tiles from `from` to `to`, if they share a row, column or
+    /// diagonal.
+    pub fn between(from: Tile, to: Tile) -> Option<(Direction, i32)> {
+        let gap = to - from;
+        let distance = gap.x.abs().max(gap.y.abs());
+
+        Direction::ALL.into_iter().find_map(|direction| {
+            let offset = direction.offset();
+            let is_along =
+                distance > 0 && offset.x * distance == gap.x && offset.y * distance == gap.y;
+            is_along.then_some((direction, distance))
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PlanetState {
+    Pending,
+    Placed(Tile),
+    Colliding(Tile),
+    /// Swallowed by a black hole or merged into another planet, gone from the board
+    Absorbed,
+}
+
+/// What a body is, beyond its gravity.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    #[default]
+    Planet,
+    /// Absorbs the planets meeting it instead of colliding with them
+    BlackHole,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 2] = [Kind::Planet, Kind::BlackHole];
+
+    /// Name used in level files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Planet => "planet",
+            Kind::BlackHole => "black_hole",
+        }
+    }
+}
+
+/// Range of a gravity field that reaches the edge of any grid
+pub const RANGE_UNLIMITED: u8 = u8::MAX;
+
+pub const MASS_DEFAULT: u8 = 1;
+pub const MASS_MAX: u8 = 5;
+
+/// The part of a planet the simulation cares about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Body {
+    pub state: PlanetState,
+    pub kind: Kind,
+
+    /// Up-left, up-right, down-left, down-right, up, down, left, right, from the highest bit
+    pub gravity_field: u8,
+    /// Tiles reached by each field, indexed by [`Direction::index`]
+    pub gravity_range: [u8; 8],
+    /// Fields pushing planets away instead of pulling them, with the same bits as
+    /// `gravity_field`
+    pub push_field: u8,
+    /// Planets are only pulled by planets at least as heavy, and the heavier side wins when
+    /// pulled both ways along a row or column
+    pub mass: u8,
+
+    pub is_removable: bool,
+    /// Gravity can't move it, while its own fields still pull and push
+    pub is_anchored: bool,
+
+    /// Movement applied by the last simulation step
+    pub sim_tile_delta: Tile,
+}
+
+impl Body {
+    pub fn new(gravity_field: u8, state: PlanetState, is_removable: bool) -> Self {
+        Self {
+            state,
+            kind: Kind::default(),
+            gravity_field,
+            gravity_range: [RANGE_UNLIMITED; 8],
+            push_field: 0,
+            mass: MASS_DEFAULT,
+            is_removable,
+            is_anchored: false,
+
+            sim_tile_delta: Tile::ZERO,
+        }
+    }
+
+    pub fn tile(&self) -> Option<Tile> {
+        match self.state {
+            PlanetState::Placed(tile) => Some(tile),
+            _ => None,
+        }
+    }
+
+    pub fn has_gravity(&self, direction: Direction) -> bool {
+        match direction {
+            Direction::Up => self.has_gravity_up(),
+            Direction::Down => self.has_gravity_down(),
+            Direction::Left => self.has_gravity_left(),
+            Direction::Right => self.has_gravity_right(),
+            Direction::UpLeft => self.has_gravity_up_left(),
+            Direction::UpRight => self.has_gravity_up_right(),
+            Direction::DownLeft => self.has_gravity_down_left(),
+            Direction::DownRight => self.has_gravity_down_right(),
+        }
+    }
+
+    pub fn gravity_range(&self, direction: Direction) -> u8 {
+        self.gravity_range[direction.index()]
+    }
+
+    /// Whether this planet's field in `direction` pushes planets away rather than pulling them
+    pub fn is_pushing(&self, direction: Direction) -> bool {
+        self.push_field & direction.bit() > 0
+    }
+
+    /// Whether this planet's field in `direction` pulls a planet `distance` tiles away
+    pub fn reaches(&self, direction: Direction, distance: i32) -> bool {
+        self.has_gravity(direction) && distance <= self.gravity_range(direction) as i32
+    }
+
+    pub fn has_gravity_up(&self) -> bool {
+        self.gravity_field & 0b1000 > 0
+    }
+    pub fn has_gravity_down(&self) -> bool {
+        self.gravity_field & 0b0100 > 0
+    }
+    pub fn has_gravity_left(&self) -> bool {
+        self.gravity_field & 0b0010 > 0
+    }
+    pub fn has_gravity_right(&self) -> bool {
+        self.gravity_field & 0b0001 > 0
+    }
+    pub fn has_gravity_up_left(&self) -> bool {
+        self.gravity_field & 0b1000_0000 > 0
+    }
+    pub fn has_gravity_up_right(&self) -> bool {
+        self.gravity_field & 0b0100_0000 > 0
+    }
+    pub fn has_gravity_down_left(&self) -> bool {
+        self.gravity_field & 0b0010_0000 > 0
+    }
+    pub fn has_gravity_down_right(&self) -> bool {
+        self.gravity_field & 0b0001_0000 > 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Board {
+    pub grid_tiles: Tile,
+    pub bodies: Vec<Body>,
+    pub rules: RuleSet,
+    /// The player moved a planet since the last step, with [`RuleSet::is_manual`]
+    pub needs_step: bool,
+}
+
+impl Board {
+    pub fn new(grid_tiles: Tile, bodies: Vec<Body>) -> Self {
+        Self {
+            grid_tiles,
+            bodies,
+            rules: RuleSet::default(),
+            needs_step: false,
+        }
+    }
+
+    pub fn is_inside(&self, tile: Tile) -> bool {
+        tile.x >= 0 && tile.y >= 0 && tile.x < self.grid_tiles.x && tile.y < self.grid_tiles.y
+    }
+
+    /// Index of the planet placed on `tile`, if any.
+    pub fn planet_at(&self, tile: Tile) -> Option<usize> {
+        self.bodies
+            .iter()
+            .position(|body| body.tile() == Some(tile))
+    }
+
+    /// Whether a placed planet sits strictly between two tiles of the same row, column or
+    /// diagonal.
+    pub fn has_planet_between(&self, from: Tile, to: Tile) -> bool {
+        let (direction, distance) = match Direction::between(from, to) {
+            None => return false,
+            Some(between) => between,
+        };
+
+        let offset = direction.offset();
+        (1..distance).any(|i| {
+            let tile = Tile::new(from.x + offset.x * i, from.y + offset.y * i);
+            self.planet_at(tile).is_some()
+        })
+    }
+
+    /// Index of the next planet the player has to place.
+    pub fn next_pending(&self) -> Option<usize> {
+        self.bodies
+            .iter()
+            .position(|body| body.state == PlanetState::Pending)
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.bodies
+            .iter()
+            .any(|body| matches!(body.state, PlanetState::Colliding(_)))
+    }
+
+    /// Tiles of the grid pulled by a planet's gravity in `direction`, nearest first. Empty if the
+    /// planet isn't placed or has no gravity that way.
+    pub fn gravity_reach(&self, planet: usize, direction: Direction) -> Vec<Tile> {
+        let body = &self.bodies[planet];
+        let mut tiles = Vec::new();
+
+        let mut tile = match body.tile() {
+            Some(tile) if body.has_gravity(direction) => tile,
+            _ => return tiles,
+        };
+        for _ in 0..body.gravity_range(direction) {
+            tile += direction.offset();
+            if !self.is_inside(tile) {
+                break;
+            }
+            tiles.push(tile);
+
+            if self.rules.reach == Reach::Nearest && self.planet_at(tile).is_some() {
+                break;
+            }
+        }
+
+        tiles
+    }
+
+    /// Every planet is placed or absorbed and the last step, run after the last move, moved none
+    /// of the placed ones.
+    pub fn is_stable(&self) -> bool {
+        !self.needs_step
+            && !self.bodies.is_empty()
+            && self.bodies.iter().all(|body| match body.state {
+                PlanetState::Placed(_) => body.sim_tile_delta == Tile::ZERO,
+                PlanetState::Absorbed => true,
+                PlanetState::Pending | PlanetState::Colliding(_) => false,
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Moved {
+        planet: usize,
+        from: Tile,
+        to: Tile,
+    },
+    Collided {
+        planet: usize,
+        tile: Tile,
+    },
+    /// Merged into `into` on `tile`, with [`Collision::Merge`] or [`Collision::Xor`]
+    Merged {
+        planet: usize,
+        into: usize,
+        tile: Tile,
+    },
+    /// Swallowed by the black hole `by` on `tile`
+    Absorbed {
+        planet: usize,
+        by: usize,
+        tile: Tile,
+    },
+    /// Moved at least partly by a field pushing it away, from `tile`
+    Pushed {
+        planet: usize,
+        tile: Tile,
+    },
+    /// Left the grid with [`Edge::Fall`] or [`Edge::Return`]
+    FellOff {
+        planet: usize,
+        tile: Tile,
+    },
+}
+
+/// Why a run of simulation steps stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Halt {
+    /// Ran the number of steps in the rules, or gave up settling after [`MAX_SETTLE_STEPS`]
+    Finished,
+    /// No planet moved in the last step
+    Settled,
+    Collided,
+    /// A planet left the grid while settling
+    LeftGrid,
+    /// Planets came back to an earlier configuration while settling, and will keep cycling
+    Oscillating,
+}
+
+/// Longest run while settling, in case planets never stop
+pub const MAX_SETTLE_STEPS: usize = 256;
+
+/// A sequence of simulation steps, such as the ones triggered by a player action.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Run {
+    /// When to stop
+    steps: Steps,
+    /// Planet states before every step so far
+    seen: Vec<Vec<PlanetState>>,
+}
+
+impl Run {
+    pub fn new(steps: Steps) -> Self {
+        Self {
+            steps,
+            seen: Vec::new(),
+        }
+    }
+
+    /// Steps run so far
+    pub fn steps(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Runs the next step, returning why the run is over once it is.
+    pub fn step(&mut self, board: &Board) -> (Board, Vec<Event>, Option<Halt>) {
+        self.seen.push(states(board));
+        let (next, events) = step(board);
+
+        let is_moving = next
+            .bodies
+            .iter()
+            .any(|body| body.sim_tile_delta != Tile::ZERO);
+
+        let halt = if next.is_failed() {
+            Some(Halt::Collided)
+        } else if !is_moving {
+            Some(Halt::Settled)
+        } else {
+            match self.steps {
+                Steps::Count(count) => (self.steps() >= count as usize).then_some(Halt::Finished),
+                Steps::Settle => {
+                    let is_outside = next
+                        .bodies
+                        .iter()
+                        .filter_map(Body::tile)
+                        .any(|tile| !next.is_inside(tile));
+
+                    if self.seen.contains(&states(&next)) {
+                        Some(Halt::Oscillating)
+                    } else if is_outside {
+                        Some(Halt::LeftGrid)
+                    } else if self.steps() >= MAX_SETTLE_STEPS {
+                        Some(Halt::Finished)
+                    } else {
+                        None
+                    }
+                }
+            }
+        };
+
+        (next, events, halt)
+    }
+}
+
+/// Runs every step of a sequence at once.
+pub fn run(board: &Board, steps: Steps) -> (Board, Vec<Event>, Halt) {
+    let mut run = Run::new(steps);
+    let mut board = board.clone();
+    let mut events = Vec::new();
+
+    loop {
+        let (next, step_events, halt) = run.step(&board);
+        board = next;
+        events.extend(step_events);
+
+        if let Some(halt) = halt {
+            return (board, events, halt);
+        }
+    }
+}
+
+fn states(board: &Board) -> Vec<PlanetState> {
+    board.bodies.iter().map(|body| body.state).collect()
+}
+
+/// Whether two planets met on the way during a step, swapping tiles or crossing paths. `before`
+/// and `after` are the planets at the start and end of the step on `board`.
+fn is_passing(board: &Board, before: (&Body, &Body), after: (&Body, &Body)) -> bool {
+    let (body, other) = after;
+    let (from, other_from) = match (before.0.tile(), before.1.tile()) {
+        (Some(from), Some(other_from)) if body.tile().is_some() && other.tile().is_some() => {
+            (from, other_from)
+        }
+        _ => return false,
+    };
+
+    // Both travel in a straight line during the step, look for a time in between where their
+    // positions match. On a wrapping grid they take the shortest way around.
+    let wrap = |offset: Tile| match board.rules.edge {
+        Edge::Wrap => Tile::new(
+            wrap_offset(offset.x, board.grid_tiles.x),
+            wrap_offset(offset.y, board.grid_tiles.y),
+        ),
+        _ => offset,
+    };
+    let gap = wrap(from - other_from);
+    let closing = wrap(body.sim_tile_delta) - wrap(other.sim_tile_delta);
+
+    let is_parallel = gap.x * closing.y == gap.y * closing.x;
+    let dot = -(gap.x * closing.x + gap.y * closing.y);
+    let length = closing.x * closing.x + closing.y * closing.y;
+
+    closing != Tile::ZERO && is_parallel && dot > 0 && dot < length
+}
+
+/// Shortest offset equivalent to `offset` along an axis that wraps every `size` tiles.
+fn wrap_offset(offset: i32, size: i32) -> i32 {
+    let offset = offset.rem_euclid(size);
+    if offset > size / 2 {
+        offset - size
+    } else {
+        offset
+    }
+}
+
+/// Advances the board by one simulation step.
+///
+/// Every placed planet is pulled one tile towards each planet in its row, column or diagonal whose
+/// gravity points at it and reaches that far, or pushed one tile away if that field pushes, unless
+/// that planet is lighter. Anchored planets don't move. Pulls from the heavier side of
+/// an axis win over the opposite ones, equally heavy pulls add up. Then planets ending on
+/// the same tile or passing through each other collide. The board's [`RuleSet`] changes how these
+/// pulls add up and resolve.
+pub fn step(board: &Board) -> (Board, Vec<Event>) {
+    let rules = &board.rules;
+    let mut next = board.clone();
+    let mut events = Vec::new();
+
+    next.needs_step = false;
+
+    // Moves computation
+    for (i, body) in next.bodies.iter_mut().enumerate() {
+        // Planets off the board don't move, even if they moved to get there
+        let tile = match body.state {
+            PlanetState::Placed(tile) => tile,
+            _ => {
+                body.sim_tile_delta = Tile::ZERO;
+                continue;
+            }
+        };
+
+        if body.is_anchored {
+            body.sim_tile_delta = Tile::ZERO;
+            continue;
+        }
+
+        let mut pulls = Vec::new();
+        for (j, other) in board.bodies.iter().enumerate() {
+            let other_tile = match other.state {
+                PlanetState::Placed(other_tile) if i != j => other_tile,
+                _ => continue,
+            };
+
+            // Heavier planets resist lighter ones
+            if other.mass < body.mass {
+                continue;
+            }
+
+            if rules.reach == Reach::Nearest && board.has_planet_between(other_tile, tile) {
+                continue;
+            }
+
+            // Row, column or diagonal gravity, pulling back towards the other planet or pushing
+            // further away
+            if let Some((direction, distance)) = Direction::between(other_tile, tile) {
+                if other.reaches(direction, distance) {
+                    let is_push = other.is_pushing(direction);
+                    let pull = if is_push {
+                        direction.offset()
+                    } else {
+                        Tile::ZERO - direction.offset()
+                    };
+                    pulls.push((pull, other.mass, is_push));
+                }
+            }
+        }
+
+        // Heaviest pull towards each side, negative then positive, along each axis
+        let mut heaviest_x = [0; 2];
+        let mut heaviest_y = [0; 2];
+        for (pull, mass, _) in &pulls {
+            if pull.x != 0 {
+                let side = &mut heaviest_x[(pull.x > 0) as usize];
+                *side = (*side).max(*mass);
+            }
+            if pull.y != 0 {
+                let side = &mut heaviest_y[(pull.y > 0) as usize];
+                *side = (*side).max(*mass);
+            }
+        }
+        let is_outweighed = |heaviest: [u8; 2], pull: i32| {
+            pull != 0 && heaviest[(pull > 0) as usize] < heaviest[(pull < 0) as usize]
+        };
+
+        let mut delta = Tile::ZERO;
+        let mut is_pushed = false;
+        for (pull, _, is_push) in &pulls {
+            // Diagonal pulls can lose along one axis and still win along the other
+            let mut pull = *pull;
+            if is_outweighed(heaviest_x, pull.x) {
+                pull.x = 0;
+            }
+            if is_outweighed(heaviest_y, pull.y) {
+                pull.y = 0;
+            }
+            is_pushed |= *is_push && pull != Tile::ZERO;
+
+            delta += pull;
+            // Clamped after every pull, so the order of the planets matters
+            if rules.is_clamped {
+                delta = Tile::new(delta.x.clamp(-1, 1), delta.y.clamp(-1, 1));
+            }
+        }
+
+        match rules.priority {
+            Priority::Both => {}
+            Priority::Row if delta.x != 0 => delta.y = 0,
+            Priority::Column if delta.y != 0 => delta.x = 0,
+            Priority::Row | Priority::Column => {}
+        }
+
+        let mut to = tile + delta;
+        let is_outside = !board.is_inside(to);
+        match rules.edge {
+            Edge::Open | Edge::Fall | Edge::Return => {}
+            Edge::Wall => {
+                to = Tile::new(
+                    to.x.clamp(0, board.grid_tiles.x - 1),
+                    to.y.clamp(0, board.grid_tiles.y - 1),
+                );
+                delta = to - tile;
+            }
+            Edge::Wrap => {
+                to = Tile::new(
+                    to.x.rem_euclid(board.grid_tiles.x),
+                    to.y.rem_euclid(board.grid_tiles.y),
+                );
+            }
+        }
+
+        body.sim_tile_delta = delta;
+        body.state = PlanetState::Placed(to);
+
+        if delta != Tile::ZERO {
+            events.push(Event::Moved {
+                planet: i,
+                from: tile,
+                to,
+            });
+            if is_pushed {
+                events.push(Event::Pushed { planet: i, tile });
+            }
+
+            if is_outside && matches!(rules.edge, Edge::Fall | Edge::Return) {
+                // Fixed planets can't be placed again
+                body.state = match rules.edge {
+                    Edge::Return if body.is_removable => PlanetState::Pending,
+                    _ => PlanetState::Colliding(to),
+                };
+                events.push(Event::FellOff {
+                    planet: i,
+                    tile: to,
+                });
+            }
+        }
+    }
+
+    // Collisions computation, black holes absorb the planets they meet instead and planets can
+    // merge depending on the rules
+    let is_merging = matches!(rules.collision, Collision::Merge | Collision::Xor);
+    let moved = next.bodies.clone();
+    for (i, body) in next.bodies.iter_mut().enumerate() {
+        let tile = match body.state {
+            PlanetState::Placed(tile) => tile,
+            _ => continue,
+        };
+
+        let is_meeting = |j: usize| {
+            if i == j {
+                return false;
+            }
+
+            let is_passing = match rules.collision {
+                Collision::Explode => is_passing(
+                    board,
+                    (&board.bodies[i], &board.bodies[j]),
+                    (&moved[i], &moved[j]),
+                ),
+                Collision::Swap | Collision::Merge | Collision::Xor => false,
+            };
+            moved[j].tile() == Some(tile) || is_passing
+        };
+
+        let absorbed_by = match body.kind {
+            Kind::Planet => {
+                (0..moved.len()).find(|j| moved[*j].kind == Kind::BlackHole && is_meeting(*j))
+            }
+            Kind::BlackHole => None,
+        };
+        // Planets collide with planets and black holes with black holes
+        let is_colliding = (0..moved.len()).any(|j| moved[j].kind == body.kind && is_meeting(j));
+
+        if let Some(by) = absorbed_by {
+            body.state = PlanetState::Absorbed;
+            events.push(Event::Absorbed {
+                planet: i,
+                by,
+                tile,
+            });
+        } else if is_colliding && is_merging {
+            // The first planet on the tile takes in the others
+            let meeting: Vec<usize> = (0..moved.len())
+                .filter(|j| moved[*j].kind == body.kind && is_meeting(*j))
+                .collect();
+            match meeting.iter().copied().filter(|j| *j < i).min() {
+                None => {
+                    for j in meeting {
+                        merge(body, &moved[j], rules.collision);
+                    }
+                }
+                Some(into) => {
+                    body.state = PlanetState::Absorbed;
+                    events.push(Event::Merged {
+                        planet: i,
+                        into,
+                        tile,
+                    });
+                }
+            }
+        } else if is_colliding {
+            body.state = PlanetState::Colliding(tile);
+            events.push(Event::Collided { planet: i, tile });
+        }
+    }
+
+    (next, events)
+}
+
+/// Makes `body` take in `other`, for [`Collision::Merge`] and [`Collision::Xor`]. Each field
+/// keeps the longest range of the planets that had it and pushes if either of them pushed.
+fn merge(body: &mut Body, other: &Body, collision: Collision) {
+    let gravity_field = match collision {
+        Collision::Xor => body.gravity_field ^ other.gravity_field,
+        _ => body.gravity_field | other.gravity_field,
+    };
+
+    for direction in Direction::ALL {
+        let index = direction.index();
+        body.gravity_range[index] =
+            match (body.has_gravity(direction), other.has_gravity(direction)) {
+                (true, true) => body.gravity_range[index].max(other.gravity_range[index]),
+                (false, true) => other.gravity_range[index],
+                _ => body.gravity_range[index],
+            };
+    }
+
+    body.push_field = (body.push_field | other.push_field) & gravity_field;
+    body.gravity_field = gravity_field;
+    body.mass = (body.mass + other.mass).min(MASS_MAX);
+    body.is_removable &= other.is_removable;
+    body.is_anchored |= other.is_anchored;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii;
+
+    fn board(source: &str) -> Board {
+        ascii::parse("test", source).unwrap().board()
+    }
+
+    fn collided(events: &[Event]) -> Vec<usize> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Collided { planet, .. } => Some(*planet),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn swapping_across_the_wrap_edge_collides() {
+        let board = board(
+            "rules: edge=wrap
+            |A..B|
+            A: gravity=0001 push=0001
+            B: gravity=0010 push=0010",
+        );
+        let (next, events) = step(&board);
+        assert_eq!(collided(&events), [0, 1]);
+        assert_eq!(
+            next.bodies[0].state,
+            PlanetState::Colliding(Tile::new(3, 0))
+        );
+    }
+
+    #[test]
+    fn swapping_neighbours_collide_unless_the_rules_swap_them() {
+        let source = "
+            |AB|
+            A: gravity=0001
+            B: gravity=0010";
+        let (next, events) = step(&board(source));
+        assert_eq!(collided(&events), [0, 1]);
+        assert_eq!(
+            next.bodies[0].state,
+            PlanetState::Colliding(Tile::new(1, 0))
+        );
+
+        let (next, events) = step(&board(&format!("rules: collision=swap{}", source)));
+        assert!(collided(&events).is_empty());
+        assert_eq!(next.bodies[0].state, PlanetState::Placed(Tile::new(1, 0)));
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(0, 0)));
+    }
+
+    #[test]
+    fn crossing_diagonals_collide() {
+        let (next, events) = step(&board(
+            "|.AB.|
+            |....|
+            |C..D|
+            A: gravity=0000
+            B: gravity=0000
+            C: gravity=01000000
+            D: gravity=10000000",
+        ));
+        assert_eq!(collided(&events), [0, 1]);
+        assert_eq!(
+            next.bodies[0].state,
+            PlanetState::Colliding(Tile::new(2, 1))
+        );
+        assert_eq!(
+            next.bodies[1].state,
+            PlanetState::Colliding(Tile::new(1, 1))
+        );
+    }
+
+    #[test]
+    fn landing_on_the_same_tile_collides() {
+        for rules in ["", "rules: collision=swap"] {
+            let (next, events) = step(&board(&format!(
+                "{}
+                |A.B|
+                A: gravity=0001
+                B: gravity=0010",
+                rules
+            )));
+            assert_eq!(collided(&events), [0, 1]);
+            assert_eq!(
+                next.bodies[1].state,
+                PlanetState::Colliding(Tile::new(1, 0))
+            );
+        }
+    }
+
+    fn tiles(board: &Board) -> Vec<Option<Tile>> {
+        board.bodies.iter().map(Body::tile).collect()
+    }
+
+    #[test]
+    fn row_gravity_pulls_along_the_row() {
+        let (next, _) = step(&board(
+            "|A...B|
+            |C....|
+            A: gravity=0001
+            B: gravity=0010
+            C: gravity=0001",
+        ));
+        assert_eq!(
+            tiles(&next),
+            [
+                Some(Tile::new(1, 0)),
+                Some(Tile::new(3, 0)),
+                Some(Tile::new(0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn column_gravity_pulls_along_the_column() {
+        let (next, _) = step(&board(
+            "|A.|
+            |..|
+            |.C|
+            |B.|
+            A: gravity=0100
+            B: gravity=0000
+            C: gravity=1000",
+        ));
+        assert_eq!(
+            tiles(&next),
+            [
+                Some(Tile::new(0, 0)),
+                Some(Tile::new(0, 2)),
+                Some(Tile::new(1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn nearest_reach_is_blocked_by_planets_in_between() {
+        let source = "
+            |A.C.B|
+            A: gravity=0001
+            B: gravity=0000
+            C: gravity=0000";
+        let blocked = board(&format!("rules: reach=nearest{}", source));
+        assert!(blocked.has_planet_between(Tile::new(0, 0), Tile::new(4, 0)));
+        assert!(!blocked.has_planet_between(Tile::new(0, 0), Tile::new(2, 0)));
+
+        let (next, _) = step(&blocked);
+        assert_eq!(
+            tiles(&next),
+            [
+                Some(Tile::new(0, 0)),
+                Some(Tile::new(4, 0)),
+                Some(Tile::new(1, 0)),
+            ]
+        );
+
+        let (next, _) = step(&board(source));
+        assert_eq!(
+            tiles(&next),
+            [
+                Some(Tile::new(0, 0)),
+                Some(Tile::new(3, 0)),
+                Some(Tile::new(1, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn nearest_reach_is_blocked_along_diagonals() {
+        let (next, _) = step(&board(
+            "rules: reach=nearest
+            |A...|
+            |....|
+            |..C.|
+            |...B|
+            A: gravity=00010000
+            B: gravity=0000
+            C: gravity=0000",
+        ));
+        assert_eq!(
+            tiles(&next),
+            [
+                Some(Tile::new(0, 0)),
+                Some(Tile::new(3, 3)),
+                Some(Tile::new(1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn black_holes_absorb_planets_without_failing() {
+        let (next, events) = step(&board(
+            "|AB|
+            A: gravity=0000
+            B: kind=black_hole gravity=0010",
+        ));
+        assert_eq!(next.bodies[0].state, PlanetState::Absorbed);
+        assert!(events.contains(&Event::Absorbed {
+            planet: 0,
+            by: 1,
+            tile: Tile::new(1, 0),
+        }));
+        assert!(collided(&events).is_empty());
+        assert!(!next.is_failed());
+        assert!(next.is_stable());
+    }
+
+    #[test]
+    fn black_holes_collide_with_each_other() {
+        let (next, events) = step(&board(
+            "|A.B|
+            A: kind=black_hole gravity=0001
+            B: kind=black_hole gravity=0010",
+        ));
+        assert_eq!(collided(&events), [0, 1]);
+        assert!(next.is_failed());
+    }
+
+    fn merged(events: &[Event]) -> Vec<(usize, usize)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Merged { planet, into, .. } => Some((*planet, *into)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn merging_planets_join_fields_and_mass() {
+        let (next, events) = step(&board(
+            "rules: collision=merge
+            |A.B|
+            A: gravity=1011 push=1000
+            B: gravity=1110 push=0100",
+        ));
+        assert_eq!(merged(&events), [(1, 0)]);
+        assert!(collided(&events).is_empty());
+
+        let body = &next.bodies[0];
+        assert_eq!(body.state, PlanetState::Placed(Tile::new(1, 0)));
+        assert_eq!(body.gravity_field, 0b1111);
+        assert_eq!(body.push_field, 0b1100);
+        assert_eq!(body.mass, 2);
+        assert_eq!(next.bodies[1].state, PlanetState::Absorbed);
+    }
+
+    #[test]
+    fn xor_merges_cancel_shared_fields() {
+        let (next, events) = step(&board(
+            "rules: collision=xor
+            |A.B|
+            A: gravity=1011 push=1000
+            B: gravity=1110 push=0100",
+        ));
+        assert_eq!(merged(&events), [(1, 0)]);
+
+        let body = &next.bodies[0];
+        assert_eq!(body.gravity_field, 0b0101);
+        // The shared up field is gone, and its push with it
+        assert_eq!(body.push_field, 0b0100);
+    }
+
+    #[test]
+    fn planets_meeting_on_a_tile_merge_into_the_first() {
+        let (next, events) = step(&board(
+            "rules: collision=merge
+            |.D.|
+            |A.B|
+            |.C.|
+            A: gravity=0001
+            B: gravity=0010
+            C: gravity=0000
+            D: gravity=0100",
+        ));
+        assert_eq!(merged(&events), [(1, 0), (2, 0)]);
+
+        let body = &next.bodies[0];
+        assert_eq!(body.state, PlanetState::Placed(Tile::new(1, 1)));
+        assert_eq!(body.gravity_field, 0b0011);
+        assert_eq!(body.mass, 3);
+    }
+
+    #[test]
+    fn merged_mass_is_capped() {
+        let (next, _) = step(&board(
+            "rules: collision=merge
+            |A.B|
+            A: gravity=0001 mass=3
+            B: gravity=0010 mass=3",
+        ));
+        assert_eq!(next.bodies[0].mass, MASS_MAX);
+    }
+
+    #[test]
+    fn lighter_planets_cannot_pull_heavier_ones() {
+        let (next, _) = step(&board(
+            "|A..B|
+            A: gravity=0001
+            B: gravity=0000 mass=2",
+        ));
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(3, 0)));
+    }
+
+    #[test]
+    fn heavier_side_wins_opposing_pulls() {
+        let (next, _) = step(&board(
+            "|A.B.C|
+            A: gravity=0001 mass=2
+            B: gravity=0000
+            C: gravity=0010",
+        ));
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(1, 0)));
+
+        // Equally heavy, the pulls cancel out
+        let (next, _) = step(&board(
+            "|A.B.C|
+            A: gravity=0001
+            B: gravity=0000
+            C: gravity=0010",
+        ));
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(2, 0)));
+    }
+
+    #[test]
+    fn equal_pulls_add_up_and_clamp() {
+        let source = "
+            |C....|
+            |.....|
+            |A.B..|
+            A: gravity=0001
+            B: gravity=0000
+            C: gravity=00010000";
+        let (next, _) = step(&board(source));
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(1, 1)));
+
+        let (next, _) = step(&board(&format!("rules: clamp=false{}", source)));
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(0, 1)));
+    }
+
+    #[test]
+    fn diagonal_pulls_can_lose_on_one_axis() {
+        let (next, _) = step(&board(
+            "|A....|
+            |.....|
+            |..B.C|
+            A: gravity=00010000
+            B: gravity=0000
+            C: gravity=0010 mass=2",
+        ));
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(3, 1)));
+    }
+
+    fn settle(source: &str) -> (Board, Halt, usize) {
+        let board = board(&format!("rules: steps=settle\n{}", source));
+        let mut run = Run::new(board.rules.steps);
+        let mut board = board;
+        loop {
+            let (next, _, halt) = run.step(&board);
+            board = next;
+            if let Some(halt) = halt {
+                return (board, halt, run.steps());
+            }
+        }
+    }
+
+    #[test]
+    fn settling_stops_once_nothing_moves() {
+        let (next, halt, steps) = settle(
+            "|AB..|
+            A: gravity=0001 push=0001 range=2
+            B: gravity=0000",
+        );
+        assert_eq!(halt, Halt::Settled);
+        assert_eq!(steps, 3);
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(3, 0)));
+    }
+
+    #[test]
+    fn settling_stops_when_planets_cycle() {
+        let (_, halt, steps) = settle(
+            "|AB|
+            |CD|
+            A: gravity=0001
+            B: gravity=0100
+            C: gravity=1000
+            D: gravity=0010",
+        );
+        assert_eq!(halt, Halt::Oscillating);
+        assert_eq!(steps, 2);
+    }
+
+    #[test]
+    fn settling_stops_when_a_planet_leaves_the_grid() {
+        let (next, halt, steps) = settle(
+            "|AB..|
+            A: gravity=0001 push=0001
+            B: gravity=0000",
+        );
+        assert_eq!(halt, Halt::LeftGrid);
+        assert_eq!(steps, 3);
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(4, 0)));
+    }
+
+    #[test]
+    fn settling_gives_up_after_max_settle_steps() {
+        // Both chase each other to the right, far enough to never reach the edge
+        let (_, halt, steps) = settle(&format!(
+            "|A.B{}|
+            A: gravity=0001 push=0001
+            B: gravity=0010",
+            ".".repeat(MAX_SETTLE_STEPS + 8)
+        ));
+        assert_eq!(halt, Halt::Finished);
+        assert_eq!(steps, MAX_SETTLE_STEPS);
+    }
+
+    #[test]
+    fn walls_stop_planets_at_the_border() {
+        let (next, events) = step(&board(
+            "rules: edge=wall
+            |AB.|
+            A: gravity=0001 push=0001
+            B: gravity=0000",
+        ));
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(2, 0)));
+
+        let (next, events_after) = step(&next);
+        assert!(!events.is_empty());
+        assert!(events_after.is_empty());
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(2, 0)));
+        assert!(next.is_stable());
+    }
+
+    #[test]
+    fn falling_off_the_grid_fails() {
+        let (next, events) = step(&board(
+            "rules: edge=fall
+            |AB|
+            A: gravity=0001 push=0001
+            B: gravity=0000",
+        ));
+        assert!(events.contains(&Event::FellOff {
+            planet: 1,
+            tile: Tile::new(2, 0),
+        }));
+        assert_eq!(
+            next.bodies[1].state,
+            PlanetState::Colliding(Tile::new(2, 0))
+        );
+        assert!(next.is_failed());
+    }
+
+    #[test]
+    fn returning_planets_go_back_to_the_stack() {
+        let source = "
+            |AB|
+            A: gravity=0001 push=0001
+            B: gravity=0000";
+        let (next, events) = step(&board(&format!("rules: edge=return{}", source)));
+        assert!(events.contains(&Event::FellOff {
+            planet: 1,
+            tile: Tile::new(2, 0),
+        }));
+        assert_eq!(next.bodies[1].state, PlanetState::Pending);
+        assert!(!next.is_failed());
+
+        // Fixed planets are lost instead
+        let source = source.replace("B: gravity=0000", "B: gravity=0000 removable=false");
+        let (next, _) = step(&board(&format!("rules: edge=return{}", source)));
+        assert_eq!(
+            next.bodies[1].state,
+            PlanetState::Colliding(Tile::new(2, 0))
+        );
+    }
+
+    #[test]
+    fn pushing_fields_move_planets_away() {
+        let (next, events) = step(&board(
+            "|.AB.|
+            A: gravity=0011 push=0001
+            B: gravity=0000",
+        ));
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(3, 0)));
+        assert!(events.contains(&Event::Pushed {
+            planet: 1,
+            tile: Tile::new(2, 0),
+        }));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, Event::Pushed { planet: 0, .. })));
+    }
+
+    #[test]
+    fn anchored_planets_stay_put_and_still_pull() {
+        let source = "
+            |A.B|
+            A: gravity=0001
+            B: gravity=0010";
+        let (_, events) = step(&board(source));
+        assert_eq!(collided(&events), [0, 1]);
+
+        let (next, events) = step(&board(
+            &source.replace("A: gravity=0001", "A: gravity=0001 anchored=true"),
+        ));
+        assert!(collided(&events).is_empty());
+        assert_eq!(next.bodies[0].state, PlanetState::Placed(Tile::new(0, 0)));
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(1, 0)));
+    }
+
+    #[test]
+    fn gravity_stops_at_its_range() {
+        let board = board(
+            "|A.BC.|
+            A: gravity=0001 range=2
+            B: gravity=0000
+            C: gravity=0000",
+        );
+        assert!(board.bodies[0].reaches(Direction::Right, 2));
+        assert!(!board.bodies[0].reaches(Direction::Right, 3));
+
+        let (next, _) = step(&board);
+        assert_eq!(next.bodies[1].state, PlanetState::Placed(Tile::new(1, 0)));
+        assert_eq!(next.bodies[2].state, PlanetState::Placed(Tile::new(3, 0)));
+    }
+
+    #[test]
+    fn gravity_reach_lists_the_pulled_tiles() {
+        let source = "
+            |.A.B.|
+            A: gravity=0011
+            B: gravity=0000";
+        let right = |board: &Board| board.gravity_reach(0, Direction::Right);
+
+        let unlimited = board(source);
+        assert_eq!(
+            right(&unlimited),
+            [Tile::new(2, 0), Tile::new(3, 0), Tile::new(4, 0)]
+        );
+        assert_eq!(
+            unlimited.gravity_reach(0, Direction::Left),
+            [Tile::new(0, 0)]
+        );
+        assert!(unlimited.gravity_reach(0, Direction::Up).is_empty());
+
+        let ranged = board(&source.replace("gravity=0011", "gravity=0011 range=4,4,4,2"));
+        assert_eq!(right(&ranged), [Tile::new(2, 0), Tile::new(3, 0)]);
+
+        let nearest = board(&format!("rules: reach=nearest{}", source));
+        assert_eq!(right(&nearest), [Tile::new(2, 0), Tile::new(3, 0)]);
+    }
+}
@@ -0,0 +1,313 @@
+//! Breadth-first search over player actions, using the same rules as the game.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    rules::Steps,
+    sim::{self, Board, Body, Event, Halt, PlanetState, Tile},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Place {
+        planet: usize,
+        tile: Tile,
+    },
+    Remove {
+        planet: usize,
+    },
+    /// Runs one simulation step, with [`crate::rules::RuleSet::is_manual`]
+    Step,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Longest action sequence to look for
+    pub max_depth: usize,
+    /// Distinct boards to visit before giving up
+    pub max_states: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 16,
+            max_states: 500_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Shortest action sequence that leaves the system stable
+    Solved(Vec<Action>),
+    /// Every reachable board was visited and none is stable
+    Unsolvable,
+    /// The limits were hit before finding a solution
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+    pub outcome: Outcome,
+    /// Distinct boards visited
+    pub states: usize,
+    /// Average number of actions available on each expanded board
+    pub branching: f32,
+}
+
+/// Actions the player can take on `board`, in the same order they are tried by the solver.
+///
+/// While there are pending planets the next one can be placed on any free tile, once every
+/// planet is placed a removable one can be picked up again. With manual rules the simulation can
+/// also be stepped at any time.
+pub fn actions(board: &Board) -> Vec<Action> {
+    if board.is_failed() || board.is_stable() {
+        return Vec::new();
+    }
+
+    let mut actions = Vec::new();
+    if let Some(planet) = board.next_pending() {
+        for y in 0..board.grid_tiles.y {
+            for x in 0..board.grid_tiles.x {
+                let tile = Tile::new(x, y);
+                if board.planet_at(tile).is_none() {
+                    actions.push(Action::Place { planet, tile });
+                }
+            }
+        }
+    } else {
+        let removable = board.bodies.iter().enumerate().filter(|(_, body)| {
+            body.is_removable && body.tile().is_some_and(|tile| board.is_inside(tile))
+        });
+        actions.extend(removable.map(|(planet, _)| Action::Remove { planet }));
+    }
+
+    if board.rules.is_manual {
+        actions.push(Action::Step);
+    }
+
+    actions
+}
+
+/// Applies a player action and the simulation steps it triggers.
+pub fn apply(board: &Board, action: Action) -> (Board, Vec<Event>, Halt) {
+    let mut board = board.clone();
+    match action {
+        Action::Place { planet, tile } => board.bodies[planet].state = PlanetState::Placed(tile),
+        Action::Remove { planet } => board.bodies[planet].state = PlanetState::Pending,
+        Action::Step => return sim::run(&board, Steps::Count(1)),
+    }
+
+    if board.rules.is_manual {
+        board.needs_step = true;
+        return (board, Vec::new(), Halt::Finished);
+    }
+
+    let steps = board.rules.steps;
+    sim::run(&board, steps)
+}
+
+pub fn solve(board: &Board, limits: Limits) -> Search {
+    let mut solver = Solver::new(board, limits);
+    loop {
+        if let Some(search) = solver.run(usize::MAX) {
+            return search;
+        }
+    }
+}
+
+/// A [`solve`] that can be run a few boards at a time, so it can be spread over several frames.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    limits: Limits,
+    /// Every visited board, with the index of the board it was reached from
+    nodes: Vec<(Option<usize>, Option<Action>)>,
+    visited: HashSet<Vec<Body>>,
+    queue: VecDeque<(Board, usize, usize)>,
+    is_cut_off: bool,
+    expanded: usize,
+    branches: usize,
+    /// Set once the search is over
+    search: Option<Search>,
+}
+
+impl Solver {
+    pub fn new(board: &Board, limits: Limits) -> Self {
+        let search = board.is_stable().then(|| Search {
+            outcome: Outcome::Solved(Vec::new()),
+            states: 1,
+            branching: 0.0,
+        });
+
+        Self {
+            limits,
+            nodes: vec![(None, None)],
+            visited: HashSet::from([key(board)]),
+            queue: VecDeque::from([(board.clone(), 0, 0)]),
+            is_cut_off: false,
+            expanded: 0,
+            branches: 0,
+            search,
+        }
+    }
+
+    /// Expands up to `max_boards` more boards, returning the result once the search is over.
+    pub fn run(&mut self, max_boards: usize) -> Option<Search> {
+        for _ in 0..max_boards {
+            if self.search.is_some() {
+                break;
+            }
+            self.expand_next();
+        }
+
+        self.search.clone()
+    }
+
+    fn expand_next(&mut self) {
+        let (board, node, depth) = match self.queue.pop_front() {
+            Some(next) => next,
+            None => {
+                self.search = Some(Search {
+                    outcome: if self.is_cut_off {
+                        Outcome::Unknown
+                    } else {
+                        Outcome::Unsolvable
+                    },
+                    states: self.visited.len(),
+                    branching: branching(self.expanded, self.branches),
+                });
+                return;
+            }
+        };
+
+        let actions = actions(&board);
+        if actions.is_empty() {
+            return;
+        }
+        if depth >= self.limits.max_depth || self.visited.len() >= self.limits.max_states {
+            self.is_cut_off = true;
+            return;
+        }
+
+        self.expanded += 1;
+        self.branches += actions.len();
+
+        for action in actions {
+            let (next, _, halt) = apply(&board, action);
+
+            // Checked before deduplicating, the same planets may have been reached by a move
+            if next.is_stable() {
+                self.nodes.push((Some(node), Some(action)));
+                self.search = Some(Search {
+                    outcome: Outcome::Solved(path(&self.nodes, self.nodes.len() - 1)),
+                    states: self.visited.len() + 1,
+                    branching: branching(self.expanded, self.branches),
+                });
+                return;
+            }
+
+            // The player can't act on a system that keeps cycling
+            if halt == Halt::Oscillating || !self.visited.insert(key(&next)) {
+                continue;
+            }
+
+            self.nodes.push((Some(node), Some(action)));
+            self.queue
+                .push_back((next, self.nodes.len() - 1, depth + 1));
+        }
+    }
+}
+
+/// The last step's movement doesn't affect what happens next, only whether the board is stable.
+fn key(board: &Board) -> Vec<Body> {
+    board
+        .bodies
+        .iter()
+        .map(|body| Body {
+            sim_tile_delta: Tile::ZERO,
+            ..*body
+        })
+        .collect()
+}
+
+fn path(nodes: &[(Option<usize>, Option<Action>)], mut node: usize) -> Vec<Action> {
+    let mut actions = Vec::new();
+    while let (Some(parent), Some(action)) = nodes[node] {
+        actions.push(action);
+        node = parent;
+    }
+    actions.reverse();
+
+    actions
+}
+
+fn branching(expanded: usize, branches: usize) -> f32 {
+    if expanded == 0 {
+        0.0
+    } else {
+        branches as f32 / expanded as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_file::parse_level;
+
+    fn obstacle() -> Board {
+        let source = include_str!("../assets/levels/07_obstacle.level");
+        parse_level("07_obstacle.level", source).unwrap().board()
+    }
+
+    #[test]
+    fn finds_the_minimal_solution() {
+        let board = obstacle();
+        let search = solve(&board, Limits::default());
+        let actions = match search.outcome {
+            Outcome::Solved(actions) => actions,
+            outcome => panic!("expected a solution, found {:?}", outcome),
+        };
+        assert_eq!(actions.len(), 6);
+
+        let board = actions
+            .iter()
+            .fold(board, |board, action| apply(&board, *action).0);
+        assert!(board.is_stable());
+    }
+
+    #[test]
+    fn stops_at_the_limits() {
+        let board = obstacle();
+
+        let limits = Limits {
+            max_depth: 5,
+            ..Limits::default()
+        };
+        assert_eq!(solve(&board, limits).outcome, Outcome::Unknown);
+
+        let limits = Limits {
+            max_states: 10,
+            ..Limits::default()
+        };
+        let search = solve(&board, limits);
+        assert_eq!(search.outcome, Outcome::Unknown);
+        assert!(search.states < 10 + actions(&board).len());
+    }
+
+    #[test]
+    fn solver_can_run_a_few_boards_at_a_time() {
+        let board = obstacle();
+        let mut solver = Solver::new(&board, Limits::default());
+
+        let mut runs = 1;
+        let search = loop {
+            match solver.run(10) {
+                None => runs += 1,
+                Some(search) => break search,
+            }
+        };
+        assert!(runs > 1);
+        assert_eq!(search, solve(&board, Limits::default()));
+    }
+}
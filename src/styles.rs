@@ -1,3 +1,4 @@
+use akj_21::sim::Direction;
 use macroquad::color::Color;
 
 #[derive(Clone)]
@@ -60,3 +61,50 @@ pub struct Colors {
     pub blue_3: Color,
     pub blue_4: Color,
 }
+
+impl Colors {
+    /// Looks up a palette colour by field name, as used in level files.
+    pub fn by_name(&self, name: &str) -> Option<Color> {
+        let color = match name {
+            "grey_dark" => self.grey_dark,
+            "grey_mid" => self.grey_mid,
+            "grey_light" => self.grey_light,
+
+            "white" => self.white,
+
+            "black_1" => self.black_1,
+            "black_2" => self.black_2,
+
+            "red_light" => self.red_light,
+            "red_dark" => self.red_dark,
+
+            "yellow_1" => self.yellow_1,
+            "yellow_2" => self.yellow_2,
+            "yellow_3" => self.yellow_3,
+            "yellow_4" => self.yellow_4,
+
+            "blue_1" => self.blue_1,
+            "blue_2" => self.blue_2,
+            "blue_3" => self.blue_3,
+            "blue_4" => self.blue_4,
+
+            _ => return None,
+        };
+
+        Some(color)
+    }
+
+    /// Colour of the gravity overlay for fields pointing in `direction`
+    pub fn by_direction(&self, direction: Direction) -> Color {
+        match direction {
+            Direction::Up => self.blue_4,
+            Direction::Down => self.blue_2,
+            Direction::Left => self.red_light,
+            Direction::Right => self.yellow_4,
+            Direction::UpLeft => self.blue_3,
+            Direction::UpRight => self.yellow_2,
+            Direction::DownLeft => self.red_dark,
+            Direction::DownRight => self.yellow_1,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Checks that a level can be shipped: a sane initial board and at least one solution.
+
+use std::fmt;
+
+use crate::{
+    level_file::LevelDef,
+    sim::{self, Event, PlanetState, Tile},
+    solver::{self, Limits, Outcome, Search},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    OutsideGrid {
+        planet: usize,
+        tile: Tile,
+    },
+    SharedTile {
+        planet: usize,
+        other: usize,
+        tile: Tile,
+    },
+    /// Collides on the first step, before the player does anything
+    Colliding {
+        planet: usize,
+        tile: Tile,
+    },
+    Unsolvable,
+    /// The solver hit its limits without finding a solution
+    Undecided {
+        states: usize,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Issue::OutsideGrid { planet, tile } => write!(
+                f,
+                "planet {} starts outside the grid at {},{}",
+                planet, tile.x, tile.y
+            ),
+            Issue::SharedTile {
+                planet,
+                other,
+                tile,
+            } => write!(
+                f,
+                "planets {} and {} both start at {},{}",
+                other, planet, tile.x, tile.y
+            ),
+            Issue::Colliding { planet, tile } => write!(
+                f,
+                "planet {} starts colliding at {},{}",
+                planet, tile.x, tile.y
+            ),
+            Issue::Unsolvable => write!(f, "no solution exists"),
+            Issue::Undecided { states } => {
+                write!(f, "no solution found after visiting {} boards", states)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub issues: Vec<Issue>,
+    /// Only run when the initial board has no issues
+    pub search: Option<Search>,
+}
+
+impl Report {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+pub fn validate(level_def: &LevelDef, limits: Limits) -> Report {
+    let board = level_def.board();
+    let mut issues = Vec::new();
+
+    for (i, body) in board.bodies.iter().enumerate() {
+        let tile = match body.state {
+            PlanetState::Pending | PlanetState::Absorbed => continue,
+            PlanetState::Placed(tile) => tile,
+            PlanetState::Colliding(tile) => {
+                issues.push(Issue::Colliding { planet: i, tile });
+                tile
+            }
+        };
+
+        if !board.is_inside(tile) {
+            issues.push(Issue::OutsideGrid { planet: i, tile });
+        }
+
+        let other = board.bodies[..i]
+            .iter()
+            .position(|other| match other.state {
+                PlanetState::Placed(other_tile) | PlanetState::Colliding(other_tile) => {
+                    other_tile == tile
+                }
+                PlanetState::Pending | PlanetState::Absorbed => false,
+            });
+        if let Some(other) = other {
+            issues.push(Issue::SharedTile {
+                planet: i,
+                other,
+                tile,
+            });
+        }
+    }
+
+    if issues.is_empty() {
+        let (_, events) = sim::step(&board);
+        for event in events {
+            if let Event::Collided { planet, tile } = event {
+                issues.push(Issue::Colliding { planet, tile });
+            }
+        }
+    }
+
+    if !issues.is_empty() {
+        return Report {
+            issues,
+            search: None,
+        };
+    }
+
+    let search = solver::solve(&board, limits);
+    match search.outcome {
+        Outcome::Solved(_) => {}
+        Outcome::Unsolvable => issues.push(Issue::Unsolvable),
+        Outcome::Unknown => issues.push(Issue::Undecided {
+            states: search.states,
+        }),
+    }
+
+    Report {
+        issues,
+        search: Some(search),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii;
+
+    fn report(source: &str) -> Report {
+        validate(&ascii::parse("test", source).unwrap(), Limits::default())
+    }
+
+    #[test]
+    fn planets_colliding_on_the_first_step_are_reported() {
+        let report = report(
+            "|AB.|
+            -: gravity=0000
+            A: gravity=0001 removable=false
+            B: gravity=0010 removable=false",
+        );
+        assert_eq!(
+            report.issues,
+            [
+                Issue::Colliding {
+                    planet: 1,
+                    tile: Tile::new(1, 0)
+                },
+                Issue::Colliding {
+                    planet: 2,
+                    tile: Tile::new(0, 0)
+                },
+            ]
+        );
+        assert_eq!(report.search, None);
+    }
+
+    #[test]
+    fn planets_at_rest_are_searched() {
+        let report = report(
+            "|A..|
+            -: gravity=0000
+            A: gravity=0000 removable=false",
+        );
+        assert!(report.is_valid());
+        assert!(report.search.is_some());
+    }
+}
@@ -0,0 +1,26 @@
+use std::path::Path;
+
+use akj_21::{level_file, solver::Limits, validate};
+
+#[test]
+fn shipped_levels_are_valid() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/levels");
+    let levels = level_file::load_dir(&dir).unwrap_or_else(|error| panic!("{}", error));
+    assert!(!levels.is_empty());
+
+    for level_def in &levels {
+        let report = validate::validate(level_def, Limits::default());
+        let issues: Vec<String> = report
+            .issues
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        assert!(
+            report.is_valid(),
+            "{}: {}\n{}",
+            level_def.name,
+            issues.join(", "),
+            level_def.board()
+        );
+    }
+}